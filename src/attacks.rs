@@ -0,0 +1,383 @@
+//! precomputed attack tables
+//!
+//! knight, king and pawn attacks are simple lookups by square. bishop and rook attacks use magic
+//! bitboards, with the magic numbers found at startup by a seeded random search so the tables
+//! come out the same every run. queens are the union of the two.
+use crate::bitboard::*;
+use crate::constants::*;
+use std::sync::OnceLock;
+
+const ROOK_DIRECTIONS: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+const BISHOP_DIRECTIONS: [(isize, isize); 4] = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
+const KNIGHT_STEPS: [(isize, isize); 8] = [
+    (-2, -1),
+    (-2, 1),
+    (-1, -2),
+    (-1, 2),
+    (2, -1),
+    (2, 1),
+    (1, -2),
+    (1, 2),
+];
+const KING_STEPS: [(isize, isize); 8] = [
+    (-1, 0),
+    (1, 0),
+    (0, -1),
+    (0, 1),
+    (-1, -1),
+    (-1, 1),
+    (1, -1),
+    (1, 1),
+];
+
+#[derive(Clone, Copy, Default)]
+struct Magic {
+    /// the squares whose occupancy changes the attacks, edges excluded
+    mask: u64,
+    magic: u64,
+    shift: u32,
+    /// where this square's attacks start in the shared sliding table
+    offset: usize,
+}
+
+impl Magic {
+    fn index(&self, occupied: u64) -> usize {
+        return self.offset
+            + ((occupied & self.mask).wrapping_mul(self.magic) >> self.shift) as usize;
+    }
+}
+
+struct AttackTables {
+    knight: [u64; 64],
+    king: [u64; 64],
+    pawn: [[u64; 64]; 2],
+    rook_magics: [Magic; 64],
+    bishop_magics: [Magic; 64],
+    sliding: Vec<u64>,
+    between: [[u64; 64]; 64],
+    line: [[u64; 64]; 64],
+}
+
+static ATTACK_TABLES: OnceLock<AttackTables> = OnceLock::new();
+
+fn tables() -> &'static AttackTables {
+    return ATTACK_TABLES.get_or_init(AttackTables::new);
+}
+
+/// build the tables now rather than on the first lookup
+pub fn init() {
+    tables();
+}
+
+pub fn knight_attacks(square: usize) -> u64 {
+    return tables().knight[square];
+}
+
+pub fn king_attacks(square: usize) -> u64 {
+    return tables().king[square];
+}
+
+/// the squares a pawn of the given colour on the square attacks
+pub fn pawn_attacks(square: usize, colour: i8) -> u64 {
+    return tables().pawn[colour_index(colour)][square];
+}
+
+pub fn bishop_attacks(square: usize, occupied: u64) -> u64 {
+    let tables = tables();
+    return tables.sliding[tables.bishop_magics[square].index(occupied)];
+}
+
+pub fn rook_attacks(square: usize, occupied: u64) -> u64 {
+    let tables = tables();
+    return tables.sliding[tables.rook_magics[square].index(occupied)];
+}
+
+pub fn queen_attacks(square: usize, occupied: u64) -> u64 {
+    return bishop_attacks(square, occupied) | rook_attacks(square, occupied);
+}
+
+/// the squares strictly between two squares on the same rank, file or diagonal, otherwise empty
+pub fn squares_between(from: usize, to: usize) -> u64 {
+    return tables().between[from][to];
+}
+
+/// the whole rank, file or diagonal running through both squares, otherwise empty
+pub fn line_through(from: usize, to: usize) -> u64 {
+    return tables().line[from][to];
+}
+
+/// the squares a piece of the given type and colour on the square attacks
+pub fn piece_attacks(piece: i8, square: usize, colour: i8, occupied: u64) -> u64 {
+    return match piece {
+        PAWN => pawn_attacks(square, colour),
+        KNIGHT => knight_attacks(square),
+        BISHOP => bishop_attacks(square, occupied),
+        ROOK => rook_attacks(square, occupied),
+        QUEEN => queen_attacks(square, occupied),
+        KING => king_attacks(square),
+        _ => EMPTY_BITBOARD,
+    };
+}
+
+impl AttackTables {
+    fn new() -> AttackTables {
+        let mut tables = AttackTables {
+            knight: [EMPTY_BITBOARD; 64],
+            king: [EMPTY_BITBOARD; 64],
+            pawn: [[EMPTY_BITBOARD; 64]; 2],
+            rook_magics: [Magic::default(); 64],
+            bishop_magics: [Magic::default(); 64],
+            sliding: Vec::new(),
+            between: [[EMPTY_BITBOARD; 64]; 64],
+            line: [[EMPTY_BITBOARD; 64]; 64],
+        };
+
+        for square in 0..64 {
+            tables.knight[square] = step_attacks(square, &KNIGHT_STEPS);
+            tables.king[square] = step_attacks(square, &KING_STEPS);
+            // white pawns move up the board, towards row 0
+            tables.pawn[colour_index(WHITE)][square] = step_attacks(square, &[(-1, -1), (-1, 1)]);
+            tables.pawn[colour_index(BLACK)][square] = step_attacks(square, &[(1, -1), (1, 1)]);
+        }
+
+        for square in 0..64 {
+            for direction in ROOK_DIRECTIONS.iter().chain(BISHOP_DIRECTIONS.iter()) {
+                // the full line is this ray, the opposite ray and the square itself
+                let full_line = square_bit(location_from_square(square))
+                    | ray_attacks(square, &[*direction], EMPTY_BITBOARD)
+                    | ray_attacks(square, &[(-direction.0, -direction.1)], EMPTY_BITBOARD);
+
+                let mut between = EMPTY_BITBOARD;
+                for to_square in ray_squares(square, *direction) {
+                    tables.between[square][to_square] = between;
+                    tables.line[square][to_square] = full_line;
+                    between |= 1u64 << to_square;
+                }
+            }
+        }
+
+        let mut random = Xorshift(0x9E37_79B9_7F4A_7C15);
+        for square in 0..64 {
+            tables.rook_magics[square] =
+                find_magic(square, &ROOK_DIRECTIONS, &mut tables.sliding, &mut random);
+            tables.bishop_magics[square] =
+                find_magic(square, &BISHOP_DIRECTIONS, &mut tables.sliding, &mut random);
+        }
+
+        return tables;
+    }
+}
+
+/// the squares reached by a single step in each direction, staying on the board
+fn step_attacks(square: usize, steps: &[(isize, isize)]) -> u64 {
+    let (row, column) = location_from_square(square);
+    let mut attacks = EMPTY_BITBOARD;
+
+    for step in steps {
+        let to_row = row as isize + step.0;
+        let to_column = column as isize + step.1;
+        if !(0..8).contains(&to_row) || !(0..8).contains(&to_column) {
+            continue;
+        }
+        attacks |= square_bit((to_row as usize, to_column as usize));
+    }
+    return attacks;
+}
+
+/// walk each ray until it leaves the board or hits an occupied square, which is included
+fn ray_attacks(square: usize, directions: &[(isize, isize)], occupied: u64) -> u64 {
+    let (row, column) = location_from_square(square);
+    let mut attacks = EMPTY_BITBOARD;
+
+    for direction in directions {
+        let mut to_row = row as isize + direction.0;
+        let mut to_column = column as isize + direction.1;
+        while (0..8).contains(&to_row) && (0..8).contains(&to_column) {
+            let bit = square_bit((to_row as usize, to_column as usize));
+            attacks |= bit;
+            if occupied & bit != EMPTY_BITBOARD {
+                break;
+            }
+            to_row += direction.0;
+            to_column += direction.1;
+        }
+    }
+    return attacks;
+}
+
+/// the squares along a single ray in order, moving away from the square
+fn ray_squares(square: usize, direction: (isize, isize)) -> Vec<usize> {
+    let (row, column) = location_from_square(square);
+    let mut squares = Vec::new();
+
+    let mut to_row = row as isize + direction.0;
+    let mut to_column = column as isize + direction.1;
+    while (0..8).contains(&to_row) && (0..8).contains(&to_column) {
+        squares.push(square_from_location((to_row as usize, to_column as usize)));
+        to_row += direction.0;
+        to_column += direction.1;
+    }
+    return squares;
+}
+
+/// the squares along each ray that can block it, so not counting the last square on the board
+fn relevant_occupancy_mask(square: usize, directions: &[(isize, isize)]) -> u64 {
+    let (row, column) = location_from_square(square);
+    let mut mask = EMPTY_BITBOARD;
+
+    for direction in directions {
+        let mut to_row = row as isize + direction.0;
+        let mut to_column = column as isize + direction.1;
+        while (0..8).contains(&(to_row + direction.0))
+            && (0..8).contains(&(to_column + direction.1))
+        {
+            mask |= square_bit((to_row as usize, to_column as usize));
+            to_row += direction.0;
+            to_column += direction.1;
+        }
+    }
+    return mask;
+}
+
+/// search for a magic that maps every occupancy of the mask to a slot without a bad collision,
+/// then append the filled slots to the shared table
+fn find_magic(
+    square: usize,
+    directions: &[(isize, isize)],
+    sliding: &mut Vec<u64>,
+    random: &mut Xorshift,
+) -> Magic {
+    let mask = relevant_occupancy_mask(square, directions);
+    let bits = mask.count_ones();
+    let size = 1usize << bits;
+
+    // enumerate every subset of the mask with the carry-rippler trick
+    let mut occupancies = Vec::with_capacity(size);
+    let mut attacks = Vec::with_capacity(size);
+    let mut subset = EMPTY_BITBOARD;
+    loop {
+        occupancies.push(subset);
+        attacks.push(ray_attacks(square, directions, subset));
+        subset = subset.wrapping_sub(mask) & mask;
+        if subset == EMPTY_BITBOARD {
+            break;
+        }
+    }
+
+    let mut table = vec![EMPTY_BITBOARD; size];
+    let mut used = vec![0u32; size];
+    let mut attempt = 0;
+
+    loop {
+        // sparse candidates make good magics far more often
+        let magic = random.next() & random.next() & random.next();
+        if (mask.wrapping_mul(magic) >> 56).count_ones() < 6 {
+            continue;
+        }
+
+        attempt += 1;
+        let candidate = Magic {
+            mask,
+            magic,
+            shift: 64 - bits,
+            offset: 0,
+        };
+
+        let mut collided = false;
+        for (occupied, attack) in occupancies.iter().zip(attacks.iter()) {
+            let index = candidate.index(*occupied);
+            if used[index] != attempt {
+                used[index] = attempt;
+                table[index] = *attack;
+            } else if table[index] != *attack {
+                collided = true;
+                break;
+            }
+        }
+
+        if !collided {
+            let offset = sliding.len();
+            sliding.extend_from_slice(&table);
+            return Magic {
+                offset,
+                ..candidate
+            };
+        }
+    }
+}
+
+/// small deterministic random number generator for the magic search
+struct Xorshift(u64);
+
+impl Xorshift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        return self.0.wrapping_mul(0x2545_F491_4F6C_DD1D);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::attacks::*;
+
+    #[test]
+    fn magic_attacks_match_ray_walk() {
+        // a handful of pseudo random occupancies for every square
+        let mut random = Xorshift(12345);
+        for square in 0..64 {
+            for _ in 0..64 {
+                let occupied = random.next() & random.next();
+                assert_eq!(
+                    rook_attacks(square, occupied),
+                    ray_attacks(square, &ROOK_DIRECTIONS, occupied)
+                );
+                assert_eq!(
+                    bishop_attacks(square, occupied),
+                    ray_attacks(square, &BISHOP_DIRECTIONS, occupied)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn between_and_line_tables() {
+        let a1 = square_from_location((7, 0));
+        let d4 = square_from_location((4, 3));
+        let b2 = square_from_location((6, 1));
+        let c3 = square_from_location((5, 2));
+        let b3 = square_from_location((5, 1));
+
+        assert_eq!(
+            squares_between(a1, d4),
+            square_bit((6, 1)) | square_bit((5, 2))
+        );
+        assert_eq!(squares_between(d4, a1), squares_between(a1, d4));
+        assert_eq!(squares_between(a1, b2), EMPTY_BITBOARD);
+        assert_eq!(squares_between(a1, b3), EMPTY_BITBOARD);
+
+        // the long diagonal from a1 to h8
+        assert_eq!(line_through(b2, c3), 0x0102_0408_1020_4080);
+        assert_eq!(line_through(a1, b3), EMPTY_BITBOARD);
+    }
+
+    #[test]
+    fn step_attacks_on_the_edges() {
+        // knight on a8 reaches b6 and c7, king on h1 reaches g1, g2 and h2
+        assert_eq!(knight_attacks(0), square_bit((2, 1)) | square_bit((1, 2)));
+        assert_eq!(
+            king_attacks(63),
+            square_bit((7, 6)) | square_bit((6, 6)) | square_bit((6, 7))
+        );
+        // white pawn on e2 attacks d3 and f3, black pawn on a7 attacks b6
+        assert_eq!(
+            pawn_attacks(square_from_location((6, 4)), WHITE),
+            square_bit((5, 3)) | square_bit((5, 5))
+        );
+        assert_eq!(
+            pawn_attacks(square_from_location((1, 0)), BLACK),
+            square_bit((2, 1))
+        );
+    }
+}
@@ -0,0 +1,60 @@
+//! helpers for working with u64 bitboards
+//!
+//! squares are numbered the same way as the (row, column) locations used everywhere else,
+//! so a8 is square 0, h8 is square 7 and h1 is square 63.
+use crate::constants::*;
+
+pub const EMPTY_BITBOARD: u64 = 0;
+
+pub const FILE_A: u64 = 0x0101_0101_0101_0101;
+pub const FILE_H: u64 = FILE_A << 7;
+
+pub const RANK_8: u64 = 0xFF;
+pub const RANK_1: u64 = RANK_8 << 56;
+
+/// a8 is a light square, so the light squares are the even columns of even rows and the odd
+/// columns of odd rows
+pub const LIGHT_SQUARES: u64 = 0xAA55_AA55_AA55_AA55;
+
+pub fn square_from_location(location: (usize, usize)) -> usize {
+    return location.0 * 8 + location.1;
+}
+
+pub fn location_from_square(square: usize) -> (usize, usize) {
+    return (square / 8, square % 8);
+}
+
+pub fn square_bit(location: (usize, usize)) -> u64 {
+    return 1u64 << square_from_location(location);
+}
+
+/// index into the colour bitboards, white is 0 and black is 1
+pub fn colour_index(colour: i8) -> usize {
+    return if colour == WHITE { 0 } else { 1 };
+}
+
+/// index into the piece bitboards, pawn is 0 and king is 5
+pub fn piece_index(piece: i8) -> usize {
+    return (piece - 1) as usize;
+}
+
+/// remove the lowest set square from the bitboard and return it
+pub fn pop_lsb(bitboard: &mut u64) -> usize {
+    let square = bitboard.trailing_zeros() as usize;
+    *bitboard &= *bitboard - 1;
+    return square;
+}
+
+/// iterate over every set square of a bitboard, lowest square first
+pub struct Squares(pub u64);
+
+impl Iterator for Squares {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.0 == EMPTY_BITBOARD {
+            return None;
+        }
+        return Some(pop_lsb(&mut self.0));
+    }
+}
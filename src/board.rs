@@ -1,31 +1,115 @@
-use crate::moves::Move;
-use crate::{constants::*, conversion, evaluate};
+use crate::bitboard::*;
+use crate::moves::*;
+use crate::{attacks, constants::*, conversion, evaluate, movegen, zobrist};
+
+/// how a game has ended, from the rules alone
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameOutcome {
+    /// the winner is the side that delivered mate
+    Checkmate {
+        winner: i8,
+    },
+    Stalemate,
+    ThreefoldRepetition,
+    FiftyMoveRule,
+    InsufficientMaterial,
+}
+
+/// how hard Board::validate is on a position it finds problems with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValidationMode {
+    /// any problem rejects the position
+    Strict,
+    /// castling rights and en passant squares that can't be right are dropped, anything else
+    /// still rejects the position
+    Lenient,
+}
+
+/// something that makes a position impossible, or impossible for the engine to play from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PositionProblem {
+    KingCount {
+        colour: i8,
+        count: u32,
+    },
+    PawnOnBackRank {
+        location: (usize, usize),
+    },
+    /// a castling right whose king or rook is not on its home square
+    CastlingWithoutKingAndRook {
+        colour: i8,
+        castle_side: usize,
+    },
+    /// no pawn that could just have made a double push sits in front of the en passant square
+    ImpossibleEnPassant {
+        location: (usize, usize),
+    },
+    /// the side that just moved left its own king in check
+    SideNotToMoveInCheck,
+}
+
+impl PositionProblem {
+    /// whether the lenient mode fixes this by dropping the bad field
+    pub fn is_repairable(self) -> bool {
+        return matches!(
+            self,
+            PositionProblem::CastlingWithoutKingAndRook { .. }
+                | PositionProblem::ImpossibleEnPassant { .. }
+        );
+    }
+}
+
+impl GameOutcome {
+    pub fn is_draw(self) -> bool {
+        return !matches!(self, GameOutcome::Checkmate { .. });
+    }
+
+    /// the result as written at the end of a pgn game
+    pub fn result(self) -> &'static str {
+        return match self {
+            GameOutcome::Checkmate { winner: WHITE } => "1-0",
+            GameOutcome::Checkmate { .. } => "0-1",
+            _ => "1/2-1/2",
+        };
+    }
+}
 
 #[derive(Clone)]
 pub struct PlyData {
     pub ply: i32,
+    pub halfmove_clock: u32,
+    pub plies_since_null_move: Option<u32>,
     pub side_to_move: i8,
-    pub can_castle_a1: bool,
-    pub can_castle_a8: bool,
-    pub can_castle_h1: bool,
-    pub can_castle_h8: bool,
+    pub castling_rook_files: [[Option<usize>; 2]; 2],
     en_passant_location: Option<(usize, usize)>,
+    pub zobrist_key: u64,
+    /// the piece taken by the move made from this ply, or empty
+    pub captured_piece: i8,
 }
 
 #[derive(Clone)]
 pub struct Board {
-    pub board_array: [[i8; 8]; 8],
-    pub colour_array: [[i8; 8]; 8],
-    pub white_attacks: [[bool; 8]; 8],
-    pub black_attacks: [[bool; 8]; 8],
+    /// one bitboard per piece type, indexed by `piece_index`
+    pub piece_bitboards: [u64; 6],
+    /// one bitboard per colour, indexed by `colour_index`
+    pub colour_bitboards: [u64; 2],
 
-    pub can_castle_a1: bool,
-    pub can_castle_a8: bool,
-    pub can_castle_h1: bool,
-    pub can_castle_h8: bool,
+    /// the file of the rook each side may still castle with, none once that right is gone.
+    /// indexed by colour index, then `QUEENSIDE` or `KINGSIDE`
+    pub castling_rook_files: [[Option<usize>; 2]; 2],
+    /// castling is written as the king taking its own rook, and read that way too
+    pub chess960: bool,
 
     pub en_passant_location: Option<(usize, usize)>,
+    /// zobrist hash of the position, kept up to date by every change to the board
+    pub zobrist_key: u64,
+    /// half moves played since the start of the game
     pub ply: i32,
+    /// half moves since the last capture or pawn move, for the fifty move rule
+    pub halfmove_clock: u32,
+    /// half moves since the last null move, if there has been one. positions from before it
+    /// can't be repeated
+    pub plies_since_null_move: Option<u32>,
     pub side_to_move: i8,
     pub hash_of_previous_positions: Vec<u64>,
     pub ply_record: Vec<PlyData>,
@@ -36,6 +120,31 @@ pub struct Board {
 impl Board {
     pub fn init() -> Board {
         // initialise the board with a new game
+        let mut board = Board {
+            piece_bitboards: [EMPTY_BITBOARD; 6],
+            colour_bitboards: [EMPTY_BITBOARD; 2],
+            castling_rook_files: [[Some(0), Some(7)]; 2],
+            chess960: false,
+            en_passant_location: None,
+            zobrist_key: 0,
+            ply: 0,
+            halfmove_clock: 0,
+            plies_since_null_move: None,
+            side_to_move: 1,
+            hash_of_previous_positions: Vec::new(),
+            ply_record: Vec::new(),
+            player_colour: 1,
+            move_list: Vec::new(),
+        };
+        board.set_starting_pieces();
+        board.zobrist_key = conversion::hash_board_state(&board);
+        board.start_position_history();
+
+        return board;
+    }
+
+    /// place the pieces for a new game, leaving everything else alone
+    fn set_starting_pieces(&mut self) {
         let board_array = [
             [4, 2, 3, 5, 6, 3, 2, 4],
             [1, 1, 1, 1, 1, 1, 1, 1],
@@ -58,221 +167,281 @@ impl Board {
             [1, 1, 1, 1, 1, 1, 1, 1],
         ];
 
-        let white_attacks = [[false; 8]; 8];
-
-        let black_attacks = [[false; 8]; 8];
+        self.piece_bitboards = [EMPTY_BITBOARD; 6];
+        self.colour_bitboards = [EMPTY_BITBOARD; 2];
 
-        return Board {
-            board_array,
-            colour_array,
-            white_attacks,
-            black_attacks,
-            can_castle_a1: true,
-            can_castle_a8: true,
-            can_castle_h1: true,
-            can_castle_h8: true,
-            en_passant_location: None,
-            ply: 0,
-            side_to_move: 1,
-            hash_of_previous_positions: Vec::new(),
-            ply_record: Vec::new(),
-            player_colour: 1,
-            move_list: Vec::new(),
-        };
+        for row in 0..8 {
+            for column in 0..8 {
+                self.set_piece_and_colour(
+                    (row, column),
+                    board_array[row][column],
+                    colour_array[row][column],
+                );
+            }
+        }
     }
 
     pub fn get_piece(&self, location: (usize, usize)) -> i8 {
-        return self.board_array[location.0][location.1];
+        let square = square_bit(location);
+        if self.occupied() & square == EMPTY_BITBOARD {
+            return EMPTY;
+        }
+        for piece in PAWN..=KING {
+            if self.piece_bitboards[piece_index(piece)] & square != EMPTY_BITBOARD {
+                return piece;
+            }
+        }
+        return EMPTY;
     }
     pub fn get_piece_colour(&self, location: (usize, usize)) -> i8 {
-        return self.colour_array[location.0 as usize][location.1 as usize];
+        let square = square_bit(location);
+        if self.colour_bitboards[colour_index(WHITE)] & square != EMPTY_BITBOARD {
+            return WHITE;
+        }
+        if self.colour_bitboards[colour_index(BLACK)] & square != EMPTY_BITBOARD {
+            return BLACK;
+        }
+        return EMPTY;
     }
     pub fn set_piece_and_colour(&mut self, location: (usize, usize), piece: i8, colour: i8) {
-        self.board_array[location.0][location.1] = piece;
-        self.colour_array[location.0][location.1] = colour;
+        let square = square_bit(location);
+
+        // hash out whatever was on the square before, and hash in the new piece
+        let previous_piece = self.get_piece(location);
+        if previous_piece != EMPTY {
+            self.zobrist_key ^=
+                zobrist::piece_key(previous_piece, self.get_piece_colour(location), location);
+        }
+        if piece != EMPTY && colour != EMPTY {
+            self.zobrist_key ^= zobrist::piece_key(piece, colour, location);
+        }
+
+        // clear whatever was on the square before
+        for bitboard in self.piece_bitboards.iter_mut() {
+            *bitboard &= !square;
+        }
+        for bitboard in self.colour_bitboards.iter_mut() {
+            *bitboard &= !square;
+        }
+
+        if piece == EMPTY || colour == EMPTY {
+            return;
+        }
+        self.piece_bitboards[piece_index(piece)] |= square;
+        self.colour_bitboards[colour_index(colour)] |= square;
+    }
+
+    /// all the squares with a piece on them
+    pub fn occupied(&self) -> u64 {
+        return self.colour_bitboards[0] | self.colour_bitboards[1];
+    }
+
+    /// all the squares with a piece of the given colour on them
+    pub fn colour_bitboard(&self, colour: i8) -> u64 {
+        return self.colour_bitboards[colour_index(colour)];
+    }
+
+    /// all the squares with the given piece type of the given colour on them
+    pub fn piece_bitboard(&self, piece: i8, colour: i8) -> u64 {
+        return self.piece_bitboards[piece_index(piece)]
+            & self.colour_bitboards[colour_index(colour)];
+    }
+
+    /// the pieces of the given colour attacking the square
+    pub fn attackers_to(&self, square: usize, colour: i8) -> u64 {
+        return movegen::attackers_to(self, square, colour, self.occupied());
+    }
+
+    /// the pieces of either colour attacking the square
+    pub fn all_attackers_to(&self, square: usize) -> u64 {
+        return self.attackers_to(square, WHITE) | self.attackers_to(square, BLACK);
+    }
+
+    /// the enemy pieces giving check to the side to move
+    pub fn checkers(&self) -> u64 {
+        return match self.get_king_location(self.side_to_move) {
+            Some(king_location) => {
+                self.attackers_to(square_from_location(king_location), -self.side_to_move)
+            }
+            None => EMPTY_BITBOARD,
+        };
+    }
+
+    /// the pieces of the given colour that can't leave the line between their king and an
+    /// enemy slider
+    pub fn pinned_pieces(&self, colour: i8) -> u64 {
+        return match self.get_king_location(colour) {
+            Some(king_location) => {
+                movegen::get_pinned_pieces(self, colour, square_from_location(king_location))
+            }
+            None => EMPTY_BITBOARD,
+        };
+    }
+
+    /// every square a piece of the given colour attacks, including squares with its own pieces
+    /// on them
+    pub fn attack_map(&self, colour: i8) -> u64 {
+        let occupied = self.occupied();
+        let mut attack_map = EMPTY_BITBOARD;
+        for piece in PAWN..=KING {
+            for square in Squares(self.piece_bitboard(piece, colour)) {
+                attack_map |= attacks::piece_attacks(piece, square, colour, occupied);
+            }
+        }
+        return attack_map;
+    }
+
+    /// the file of the rook the colour can still castle with on that side, if it can
+    pub fn castling_rook_file(&self, colour: i8, castle_side: usize) -> Option<usize> {
+        return self.castling_rook_files[colour_index(colour)][castle_side];
     }
 
+    /// the position before any of the moves in the move list were made
+    pub fn game_start(&self) -> Board {
+        let mut start = self.clone();
+        for chess_move in self.move_list.iter().rev() {
+            if chess_move.is_null() {
+                start.un_make_null_move();
+            } else {
+                start.un_make_move(*chess_move);
+            }
+        }
+        return start;
+    }
+
+    pub fn from_fen(fen: &str) -> Result<Board, conversion::FenError> {
+        return conversion::parse_fen(fen);
+    }
     pub fn get_fen(&self) -> String {
-        return "".to_string();
+        return conversion::convert_board_to_fen(self);
     }
     pub fn reset_board(&mut self) {
-        self.board_array = [
-            [4, 2, 3, 5, 6, 3, 2, 4],
-            [1, 1, 1, 1, 1, 1, 1, 1],
-            [0, 0, 0, 0, 0, 0, 0, 0],
-            [0, 0, 0, 0, 0, 0, 0, 0],
-            [0, 0, 0, 0, 0, 0, 0, 0],
-            [0, 0, 0, 0, 0, 0, 0, 0],
-            [1, 1, 1, 1, 1, 1, 1, 1],
-            [4, 2, 3, 5, 6, 3, 2, 4],
-        ];
-        self.colour_array = [
-            [-1, -1, -1, -1, -1, -1, -1, -1],
-            [-1, -1, -1, -1, -1, -1, -1, -1],
-            [0, 0, 0, 0, 0, 0, 0, 0],
-            [0, 0, 0, 0, 0, 0, 0, 0],
-            [0, 0, 0, 0, 0, 0, 0, 0],
-            [0, 0, 0, 0, 0, 0, 0, 0],
-            [1, 1, 1, 1, 1, 1, 1, 1],
-            [1, 1, 1, 1, 1, 1, 1, 1],
-        ];
+        self.set_starting_pieces();
 
         // self.en_passant = false;
         self.en_passant_location = None;
 
-        self.can_castle_a1 = false;
-        self.can_castle_h1 = false;
-
-        self.can_castle_a8 = false;
-        self.can_castle_h8 = false;
+        self.castling_rook_files = [[None; 2]; 2];
 
         self.ply = 0;
+        self.halfmove_clock = 0;
+        self.plies_since_null_move = None;
         self.side_to_move = 1;
         self.hash_of_previous_positions = Vec::new();
         self.ply_record = Vec::new();
         self.move_list = Vec::new();
         self.player_colour = 1;
+        self.zobrist_key = conversion::hash_board_state(self);
+        self.start_position_history();
     }
     fn _clear_hash_of_previous_positions(&mut self) {
         self.hash_of_previous_positions = Vec::new();
     }
 
+    /// the history of a game that starts from the position on the board, which counts towards
+    /// its own repetitions like any other
+    pub fn start_position_history(&mut self) {
+        self.hash_of_previous_positions = vec![self.zobrist_key];
+    }
+
     fn add_hash_of_current_position(&mut self) {
-        self.hash_of_previous_positions
-            .push(conversion::hash_board_state(self));
+        self.hash_of_previous_positions.push(self.zobrist_key);
     }
 
     pub fn clear_board(&mut self) {
         self.reset_board();
 
         // set all squares to empty
-        self.board_array = [
-            [0, 0, 0, 0, 0, 0, 0, 0],
-            [0, 0, 0, 0, 0, 0, 0, 0],
-            [0, 0, 0, 0, 0, 0, 0, 0],
-            [0, 0, 0, 0, 0, 0, 0, 0],
-            [0, 0, 0, 0, 0, 0, 0, 0],
-            [0, 0, 0, 0, 0, 0, 0, 0],
-            [0, 0, 0, 0, 0, 0, 0, 0],
-            [0, 0, 0, 0, 0, 0, 0, 0],
-        ];
-        self.colour_array = [
-            [0, 0, 0, 0, 0, 0, 0, 0],
-            [0, 0, 0, 0, 0, 0, 0, 0],
-            [0, 0, 0, 0, 0, 0, 0, 0],
-            [0, 0, 0, 0, 0, 0, 0, 0],
-            [0, 0, 0, 0, 0, 0, 0, 0],
-            [0, 0, 0, 0, 0, 0, 0, 0],
-            [0, 0, 0, 0, 0, 0, 0, 0],
-            [0, 0, 0, 0, 0, 0, 0, 0],
-        ];
+        self.piece_bitboards = [EMPTY_BITBOARD; 6];
+        self.colour_bitboards = [EMPTY_BITBOARD; 2];
+        self.zobrist_key = conversion::hash_board_state(self);
+        self.start_position_history();
     }
 
-    pub fn make_move(&mut self, move_to_do: &Move) {
-        self.move_list.push(move_to_do.clone());
+    pub fn make_move(&mut self, move_to_do: Move) {
+        self.move_list.push(move_to_do);
+
+        let from = move_to_do.from();
+        let to = move_to_do.to();
+        let moving_piece = self.get_piece(from);
+        let side = self.side_to_move;
+
+        // the pawn taken en passant is not on the destination square,
+        // and castling "captures" our own rook
+        let captured_location = if move_to_do.is_en_passant() {
+            (from.0, to.1)
+        } else {
+            to
+        };
+        let captured_piece = if move_to_do.is_castle() {
+            EMPTY
+        } else {
+            self.get_piece(captured_location)
+        };
 
         self.ply_record.push(PlyData {
             ply: self.ply,
+            halfmove_clock: self.halfmove_clock,
+            plies_since_null_move: self.plies_since_null_move,
             side_to_move: self.side_to_move,
 
             en_passant_location: self.en_passant_location,
-            can_castle_a1: self.can_castle_a1,
-            can_castle_a8: self.can_castle_a8,
-            can_castle_h1: self.can_castle_h1,
-            can_castle_h8: self.can_castle_h8,
+            castling_rook_files: self.castling_rook_files,
+            zobrist_key: self.zobrist_key,
+            captured_piece,
         });
 
-        // if enpassant was set at board level, and a pawn just moved to an empty square, behind the en passant locaiton
-        // then remove the pawn at the en passant location.
-        if self.en_passant_location.is_some() {
-            if move_to_do.to.1 == self.en_passant_location.unwrap_or((0, 0)).1
-                && move_to_do.from_piece == PAWN
-                && move_to_do.to_piece == EMPTY
-                && self
-                    .en_passant_location
-                    .unwrap()
-                    .1
-                    .abs_diff(move_to_do.from.1)
-                    == 1
-                && self.en_passant_location.unwrap().0 == move_to_do.from.0
-            {
-                // the player is doing en passant.
-                // so remove the pawn at the en passant location
-                self.set_piece_and_colour(self.en_passant_location.unwrap(), EMPTY, EMPTY)
-            }
+        // castling and en passant keys are swapped for the new ones once the move is done
+        let previous_state_key = zobrist::castling_key(self) ^ zobrist::en_passant_key(self);
+
+        if moving_piece == PAWN || captured_piece != EMPTY {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
         }
+        self.plies_since_null_move = self.plies_since_null_move.map(|plies| plies + 1);
 
         // set board level en passant information
-        if move_to_do.en_passant {
-            self.en_passant_location = Some(move_to_do.to);
+        if move_to_do.is_double_pawn_push() {
+            self.en_passant_location = Some(to);
         } else {
             self.en_passant_location = None;
         }
 
-        // hanbdle promotion here.
-        self.set_piece_and_colour(
-            move_to_do.to,
-            move_to_do.promotion_to.unwrap_or(move_to_do.from_piece),
-            move_to_do.from_colour,
-        );
+        if let Some((king_to, rook_to)) = castling_destinations(move_to_do) {
+            // the king and rook can land on each other's squares in chess960,
+            // so take both off before putting them back
+            self.set_piece_and_colour(from, EMPTY, EMPTY);
+            self.set_piece_and_colour(to, EMPTY, EMPTY);
+            self.set_piece_and_colour(king_to, KING, side);
+            self.set_piece_and_colour(rook_to, ROOK, side);
+        } else {
+            if move_to_do.is_en_passant() {
+                self.set_piece_and_colour(captured_location, EMPTY, EMPTY);
+            }
 
-        self.set_piece_and_colour(move_to_do.from, EMPTY, EMPTY);
+            // hanbdle promotion here.
+            self.set_piece_and_colour(to, move_to_do.promotion_to().unwrap_or(moving_piece), side);
 
-        if move_to_do.from_piece == KING {
-            if move_to_do.from_colour == WHITE {
-                self.can_castle_a1 = false;
-                self.can_castle_h1 = false;
-            } else if move_to_do.from_colour == BLACK {
-                self.can_castle_a8 = false;
-                self.can_castle_h8 = false;
-            }
+            self.set_piece_and_colour(from, EMPTY, EMPTY);
         }
 
-        // even if rook moves by itself, set rook not moved to false
-        if move_to_do.from_piece == ROOK {
-            if move_to_do.from_colour == WHITE {
-                if move_to_do.from == (7, 0) {
-                    self.can_castle_a1 = false;
-                } else if move_to_do.from == (7, 7) {
-                    self.can_castle_h1 = false;
-                }
-            } else if move_to_do.from_colour == BLACK {
-                if move_to_do.from == (0, 0) {
-                    self.can_castle_a8 = false;
-                } else if move_to_do.from == (0, 7) {
-                    self.can_castle_h8 = false;
-                }
-            }
+        if moving_piece == KING {
+            self.castling_rook_files[colour_index(side)] = [None; 2];
         }
 
-        // if rook is captured by another piece, cant castle anymore
-        if move_to_do.to_piece == ROOK {
-            if move_to_do.to_colour == WHITE {
-                if move_to_do.to == (7, 0) {
-                    self.can_castle_a1 = false;
-                } else if move_to_do.to == (7, 7) {
-                    self.can_castle_h1 = false;
-                }
-            } else if move_to_do.to_colour == BLACK {
-                if move_to_do.to == (0, 0) {
-                    self.can_castle_a8 = false;
-                } else if move_to_do.to == (0, 7) {
-                    self.can_castle_h8 = false;
+        // a rook moving off its castling square, or being captured on it, loses that right
+        for colour in [WHITE, BLACK] {
+            let back_row = back_row(colour);
+            for rook_file in self.castling_rook_files[colour_index(colour)].iter_mut() {
+                if let Some(file) = *rook_file {
+                    if from == (back_row, file) || to == (back_row, file) {
+                        *rook_file = None;
+                    }
                 }
             }
         }
 
-        // If current move castling, move rook too. king alreadyt moved
-        if move_to_do.castle_from_to_square.is_some() {
-            let castle_from_to_square = move_to_do.castle_from_to_square.unwrap();
-
-            // this castleFromToSquare needs to be set to the rook movements, right now its the kings.
-            // set destination square
-            self.set_piece_and_colour(castle_from_to_square.1, ROOK, move_to_do.from_colour);
-
-            self.set_piece_and_colour(castle_from_to_square.0, EMPTY, EMPTY);
-        }
-
         // set side to move to opposite
         self.side_to_move = if self.side_to_move == WHITE {
             BLACK
@@ -280,145 +449,184 @@ impl Board {
             WHITE
         };
 
+        self.zobrist_key ^= zobrist::ZOBRIST_KEYS.black_to_move
+            ^ previous_state_key
+            ^ zobrist::castling_key(self)
+            ^ zobrist::en_passant_key(self);
+        debug_assert_eq!(
+            self.zobrist_key,
+            conversion::hash_board_state(self),
+            "incremental hash diverged after make_move"
+        );
+
         self.add_hash_of_current_position();
 
         self.ply += 1;
     }
 
-    /// make move does not validate the move, it just does it, overwritting the destination square
-    pub fn make_move_with_notation(&mut self, chess_move: String) -> Result<Move, String> {
-        let move_to_do_result = self.convert_notation_to_move(chess_move);
-        let move_to_do = match move_to_do_result {
-            Err(e) => return Err(e),
-            Ok(m) => m,
-        };
+    /// pass the turn without moving. the move list gets a null move so it stays in step with
+    /// the ply record
+    pub fn make_null_move(&mut self) {
+        self.move_list.push(Move::NULL);
+        self.ply_record.push(PlyData {
+            ply: self.ply,
+            halfmove_clock: self.halfmove_clock,
+            plies_since_null_move: self.plies_since_null_move,
+            side_to_move: self.side_to_move,
+            en_passant_location: self.en_passant_location,
+            castling_rook_files: self.castling_rook_files,
+            zobrist_key: self.zobrist_key,
+            captured_piece: EMPTY,
+        });
 
-        // validate move
-        if move_to_do.from_piece == EMPTY {
-            return Err("cannot move empty square".to_string());
-        }
-        if move_to_do.from_colour == EMPTY {
-            return Err("cannot move empty colour".to_string());
-        }
+        // the en passant square only lasts for the one move
+        self.zobrist_key ^= zobrist::en_passant_key(self) ^ zobrist::ZOBRIST_KEYS.black_to_move;
+        self.en_passant_location = None;
+        self.side_to_move = -self.side_to_move;
+        self.halfmove_clock += 1;
+        self.plies_since_null_move = Some(0);
+        debug_assert_eq!(
+            self.zobrist_key,
+            conversion::hash_board_state(self),
+            "incremental hash diverged after make_null_move"
+        );
 
-        self.make_move(&move_to_do);
-        return Ok(move_to_do);
+        self.add_hash_of_current_position();
+
+        self.ply += 1;
     }
 
-    /// make move does not validate the move, it just does it, overwritting the destination square
-    pub fn un_make_move(&mut self, chess_move: &Move) {
+    pub fn un_make_null_move(&mut self) {
         self.move_list.pop();
-        // the move should retain the original pieces in each square.
-        self.set_piece_and_colour(chess_move.to, chess_move.to_piece, chess_move.to_colour);
+        self.hash_of_previous_positions.pop();
 
-        self.set_piece_and_colour(
-            chess_move.from,
-            chess_move.from_piece,
-            chess_move.from_colour,
-        );
+        let Some(previous_ply_data) = self.ply_record.pop() else {
+            return;
+        };
+        self.ply = previous_ply_data.ply;
+        self.halfmove_clock = previous_ply_data.halfmove_clock;
+        self.plies_since_null_move = previous_ply_data.plies_since_null_move;
+        self.side_to_move = previous_ply_data.side_to_move;
+        self.en_passant_location = previous_ply_data.en_passant_location;
+        self.zobrist_key = previous_ply_data.zobrist_key;
+    }
 
-        // set rooks back
-        if chess_move.castle_from_to_square.is_some() {
-            let castle_from_to_square = chess_move.castle_from_to_square.unwrap();
+    pub fn is_pseudo_legal(&self, chess_move: Move) -> bool {
+        return movegen::is_pseudo_legal(self, chess_move);
+    }
+    pub fn is_legal(&self, chess_move: Move) -> bool {
+        return movegen::is_legal(self, chess_move);
+    }
+    pub fn gives_check(&self, chess_move: Move) -> bool {
+        return movegen::gives_check(self, chess_move);
+    }
 
-            // this castleFromToSquare needs to be set to the rook movements, right now its the kings.
+    /// make a move given in uci notation, as long as it is one of the legal moves
+    pub fn make_move_with_notation(&mut self, chess_move: String) -> Result<Move, MoveParseError> {
+        let move_to_do = self.parse_move(&chess_move)?;
+        self.make_move(move_to_do);
+        return Ok(move_to_do);
+    }
 
-            self.set_piece_and_colour(castle_from_to_square.0, ROOK, chess_move.from_colour);
-            self.set_piece_and_colour(castle_from_to_square.1, EMPTY, EMPTY);
+    /// the legal move written in uci notation. castling can be the king moving two squares or
+    /// taking its own rook, but only the second in chess960 where the first could be a normal
+    /// king move. a promotion without its piece matches all four, so is ambiguous
+    pub fn parse_move(&self, notation: &str) -> Result<Move, MoveParseError> {
+        let notation = notation.trim().to_ascii_lowercase();
+        let well_formed = matches!(
+            notation.as_bytes(),
+            [b'a'..=b'h', b'1'..=b'8', b'a'..=b'h', b'1'..=b'8']
+                | [
+                    b'a'..=b'h',
+                    b'1'..=b'8',
+                    b'a'..=b'h',
+                    b'1'..=b'8',
+                    b'n' | b'b' | b'r' | b'q'
+                ]
+        );
+        if !well_formed {
+            return Err(MoveParseError::Malformed(notation));
         }
 
-        // does this handle enpassant
+        let matches: Vec<Move> = movegen::generate_legal_moves(self)
+            .iter()
+            .filter(|chess_move| {
+                let written = [
+                    conversion::convert_move_to_uci_notation(chess_move, self.chess960),
+                    conversion::convert_move_to_uci_notation(chess_move, true),
+                ];
+                written.iter().any(|written| {
+                    *written == notation || (chess_move.is_promotion() && written[..4] == notation)
+                })
+            })
+            .collect();
+
+        return match matches.len() {
+            0 => Err(MoveParseError::Illegal(notation)),
+            1 => Ok(matches[0]),
+            _ => Err(MoveParseError::Ambiguous(notation)),
+        };
+    }
+
+    /// take back the last move made, using what the ply record kept from before it
+    pub fn un_make_move(&mut self, chess_move: Move) {
+        self.move_list.pop();
         self.hash_of_previous_positions.pop();
 
-        self.ply -= 1;
-        let enemy_colour = self.side_to_move;
-        self.side_to_move = if self.side_to_move == WHITE {
-            BLACK
-        } else {
-            WHITE
+        let Some(previous_ply_data) = self.ply_record.pop() else {
+            return;
         };
-        // remove last ply data
-
-        let previous_ply = self.ply_record.last();
-
-        if previous_ply.is_some() {
-            let previous_ply_data = previous_ply.unwrap();
-
-            // aply previous ply data to self.
-            self.ply = previous_ply_data.ply;
-            self.side_to_move = previous_ply_data.side_to_move;
-            self.can_castle_a1 = previous_ply_data.can_castle_a1;
-            self.can_castle_a8 = previous_ply_data.can_castle_a8;
-            self.can_castle_h1 = previous_ply_data.can_castle_h1;
-            self.can_castle_h8 = previous_ply_data.can_castle_h8;
-            self.en_passant_location = previous_ply_data.en_passant_location;
-            self.player_colour = previous_ply_data.side_to_move;
-        }
-        self.ply_record.pop();
-
-        if self.en_passant_location.is_some() {
-            if chess_move.to.1 == self.en_passant_location.unwrap_or((0, 0)).1
-                && chess_move.from_piece == PAWN
-                && chess_move.to_piece == EMPTY
-                && self
-                    .en_passant_location
-                    .unwrap()
-                    .1
-                    .abs_diff(chess_move.from.1)
-                    == 1
-                && self.en_passant_location.unwrap().0 == chess_move.from.0
-            {
-                // the player is doing en passant.
-                // so remove the pawn at the en passant location
-                self.set_piece_and_colour(self.en_passant_location.unwrap(), PAWN, enemy_colour);
+
+        let from = chess_move.from();
+        let to = chess_move.to();
+        let side = previous_ply_data.side_to_move;
+        let enemy_colour = if side == WHITE { BLACK } else { WHITE };
+
+        if let Some((king_to, rook_to)) = castling_destinations(chess_move) {
+            self.set_piece_and_colour(king_to, EMPTY, EMPTY);
+            self.set_piece_and_colour(rook_to, EMPTY, EMPTY);
+            self.set_piece_and_colour(from, KING, side);
+            self.set_piece_and_colour(to, ROOK, side);
+        } else {
+            // a promoted piece goes back to being a pawn
+            let moved_piece = if chess_move.is_promotion() {
+                PAWN
+            } else {
+                self.get_piece(to)
+            };
+
+            self.set_piece_and_colour(from, moved_piece, side);
+
+            if chess_move.is_en_passant() {
+                self.set_piece_and_colour(to, EMPTY, EMPTY);
+                self.set_piece_and_colour((from.0, to.1), PAWN, enemy_colour);
+            } else {
+                self.set_piece_and_colour(to, previous_ply_data.captured_piece, enemy_colour);
             }
         }
-        // add pawn back from en passant
+
+        // aply previous ply data to self.
+        self.ply = previous_ply_data.ply;
+        self.halfmove_clock = previous_ply_data.halfmove_clock;
+        self.plies_since_null_move = previous_ply_data.plies_since_null_move;
+        self.side_to_move = previous_ply_data.side_to_move;
+        self.castling_rook_files = previous_ply_data.castling_rook_files;
+        self.en_passant_location = previous_ply_data.en_passant_location;
+        self.player_colour = previous_ply_data.side_to_move;
+
+        self.zobrist_key = previous_ply_data.zobrist_key;
+        debug_assert_eq!(
+            self.zobrist_key,
+            conversion::hash_board_state(self),
+            "hash restored by un_make_move does not match the position"
+        );
     }
     pub fn is_piece_type_on_board_for_side(&self, piece: i8, colour: i8) -> bool {
-        // go through each piece on the board, by colour to only get moves for side to move.
-        for (row_index, row) in self.board_array.iter().enumerate() {
-            for (column_index, piece_type) in row.iter().enumerate() {
-                if piece_type != &piece {
-                    continue;
-                }
-                let piece_colour = self.get_piece_colour((row_index, column_index));
-                if piece_colour != colour {
-                    continue;
-                }
-                return true;
-            }
-        }
-        return false;
+        return self.piece_bitboard(piece, colour) != EMPTY_BITBOARD;
     }
+    /// builds the move the notation describes from what is on the board, without checking it is
+    /// legal. input from outside should go through parse_move
     pub fn convert_notation_to_move(&self, chess_move: String) -> Result<Move, String> {
-        let mut converted_move = Move::default();
-
-        // convert "e1g1" "e1c1" "e8g8" "e8c8" into O-O or O-O-O
-        if ((chess_move == "e1g1" && self.can_castle_h1)
-            || (chess_move == "e1c1" && self.can_castle_a1))
-            || ((chess_move == "e8g8" && self.can_castle_h8)
-                || (chess_move == "e8c8" && self.can_castle_h1))
-        {
-            // castling is a special case
-            let from_to_squares = match chess_move.as_str() {
-                "e1g1" => ((7, 4), (7, 6), (7, 7), (7, 5)),
-                "e8g8" => ((0, 4), (0, 6), (0, 7), (0, 5)),
-                "e1c1" => ((7, 4), (7, 2), (7, 0), (7, 3)),
-                "e8c8" => ((0, 4), (0, 2), (0, 9), (0, 3)),
-                _ => return Err("Invalid castling move".to_string()),
-            };
-            // depends on side to move where piece is
-            return Ok(Move {
-                from: from_to_squares.0,
-                from_piece: KING,
-                from_colour: self.side_to_move,
-                to: from_to_squares.1,
-                castle_from_to_square: Some((from_to_squares.2, from_to_squares.3)),
-                ..Move::default()
-            });
-        }
-
         // should be in format e2e3
         if chess_move.len() < 4 || chess_move.len() > 5 {
             // println!("{}", chess_move.len());
@@ -467,13 +675,15 @@ impl Board {
                 _ => return Err("argument too long".to_string()),
             }
         }
+        let mut from = (0, 0);
+        let mut to = (0, 0);
+
         // get first two characters
         for (board_row_index, board_row) in BOARD_COORDINATES.iter().enumerate() {
             for (column_index, square_coordinate) in board_row.iter().enumerate() {
                 // println!("{}", square_coordinate);
                 if *square_coordinate == chess_move.get(0..2).unwrap() {
-                    converted_move.from.0 = board_row_index;
-                    converted_move.from.1 = column_index;
+                    from = (board_row_index, column_index);
                     break;
                 }
             }
@@ -483,42 +693,62 @@ impl Board {
             for (column_index, square_coordinate) in board_row.iter().enumerate() {
                 // println!("{}", square_coordinate);
                 if *square_coordinate == chess_move.get(2..4).unwrap() {
-                    converted_move.to.0 = board_row_index;
-                    converted_move.to.1 = column_index;
+                    to = (board_row_index, column_index);
                     break;
                 }
             }
         }
 
+        // the flag depends on what is on the board, not just the squares
+        let from_piece = self.get_piece(from);
+        let is_capture = self.get_piece(to) != EMPTY;
+
+        // castling is stored as the king taking its own rook. chess960 writes it that way too,
+        // normal chess writes it as the king moving two squares
+        let side = self.get_piece_colour(from);
+        if from_piece == KING && from.0 == back_row(side) && to.0 == from.0 {
+            let castle_side = if to.1 > from.1 { KINGSIDE } else { QUEENSIDE };
+            let takes_own_rook = self.piece_bitboard(ROOK, side) & square_bit(to) != EMPTY_BITBOARD;
+            let moves_two_squares = !self.chess960 && from.1.abs_diff(to.1) == 2;
+
+            if takes_own_rook || moves_two_squares {
+                let rook_file = if takes_own_rook {
+                    to.1
+                } else {
+                    self.castling_rook_file(side, castle_side)
+                        .unwrap_or(if castle_side == KINGSIDE { 7 } else { 0 })
+                };
+                let flag = if castle_side == KINGSIDE {
+                    KING_CASTLE
+                } else {
+                    QUEEN_CASTLE
+                };
+                return Ok(Move::new(from, (from.0, rook_file), flag));
+            }
+        }
+
         // handle last character as promotion
-        if chess_move.len() == 5 {
+        let flag = if chess_move.len() == 5 {
             let promotion_to = match chess_move.chars().nth(4).unwrap() {
-                'p' => 1,
-                'n' => 2,
-                'b' => 3,
-                'r' => 4,
-                'q' => 5,
-                'k' => 6,
-                _ => 0,
+                'n' => KNIGHT,
+                'b' => BISHOP,
+                'r' => ROOK,
+                'q' => QUEEN,
+                other => return Err(format!("cannot promote to: {}", other)),
             };
-            converted_move.promotion_to = Some(promotion_to);
-        }
-
-        converted_move.from_colour = self.get_piece_colour(converted_move.from);
-
-        converted_move.to_colour = self.get_piece_colour(converted_move.to);
-
-        converted_move.from_piece = self.get_piece(converted_move.from);
-        converted_move.to_piece = self.get_piece(converted_move.to);
-
-        // if piece is a pawn, check if en passant
-        if converted_move.from_piece == PAWN
-            && (converted_move.from.0 as i8 - converted_move.to.0 as i8).abs() == 2
-        {
-            converted_move.en_passant = true;
-        }
+            promotion_flag(promotion_to, is_capture)
+        } else if from_piece == PAWN && from.0.abs_diff(to.0) == 2 {
+            DOUBLE_PAWN_PUSH
+        } else if from_piece == PAWN && from.1 != to.1 && !is_capture {
+            // a pawn moving diagonally onto an empty square can only be capturing en passant
+            EN_PASSANT_CAPTURE
+        } else if is_capture {
+            CAPTURE
+        } else {
+            QUIET_MOVE
+        };
 
-        return Ok(converted_move);
+        return Ok(Move::new(from, to, flag));
     }
 
     pub fn is_square_empty(&self, square: &str) -> bool {
@@ -526,65 +756,224 @@ impl Board {
 
         return self.get_piece(coordinates) == EMPTY;
     }
-    // pub fn hash_board_state(&self) -> String {
-    //     // take board state and generate a hash to use to compare uniqueness of position
-
-    //     let mut hasher = DefaultHasher::new();
-    //     for row in self.board_array.iter() {
-    //         for square in row.iter() {
-    //             hasher.write_i8(*square);
-    //         }
-    //     }
-    //     for row in self.colour_array.iter() {
-    //         for square in row.iter() {
-    //             hasher.write_i8(*square);
-    //         }
-    //     }
-
-    //     hasher.write_i8(self.side_to_move);
-
-    //     return format!("{:x}", hasher.finish());
-    // }
     pub fn has_positions_repeated(&self) -> bool {
         // check if current hash appears two or more times in the history
-        let current_hash = conversion::hash_board_state(self);
+        let current_hash = self.zobrist_key;
+
+        // nothing from before a capture, pawn move or null move can come round again. the
+        // history holds the current position too, so look one further back than that
+        let reversible_plies = self
+            .plies_since_null_move
+            .map_or(self.halfmove_clock, |plies| plies.min(self.halfmove_clock));
+        let history_start = self
+            .hash_of_previous_positions
+            .len()
+            .saturating_sub(reversible_plies as usize + 1);
 
         //search history for this hash
-        let count = self
-            .hash_of_previous_positions
+        let count = self.hash_of_previous_positions[history_start..]
             .iter()
             .filter(|&x| *x == current_hash)
             .count();
 
-        return if count >= 3 { true } else { false };
+        return count >= 3;
     }
-    pub fn get_king_location(&self, side: i8) -> Option<(usize, usize)> {
-        // find king for side
-        // go through each piece on the board, by colour to only get moves for side to move.
-        for (row_index, row) in self.board_array.iter().enumerate() {
-            for (column_index, piece) in row.iter().enumerate() {
-                if *piece != KING {
-                    continue;
-                }
+    /// every problem with the position. strict mode returns them all as an error if there are any.
+    /// lenient mode drops the castling rights and en passant square that can't be right, and
+    /// only errors if something else is wrong, otherwise handing back what it repaired
+    pub fn validate(
+        &mut self,
+        mode: ValidationMode,
+    ) -> Result<Vec<PositionProblem>, Vec<PositionProblem>> {
+        let mut problems = Vec::new();
+
+        for colour in [WHITE, BLACK] {
+            let count = self.piece_bitboard(KING, colour).count_ones();
+            if count != 1 {
+                problems.push(PositionProblem::KingCount { colour, count });
+            }
+        }
 
-                let location = (row_index, column_index);
-                let square_colour = &self.get_piece_colour((row_index, column_index));
+        let pawns = self.piece_bitboards[piece_index(PAWN)];
+        for square in Squares(pawns & (RANK_1 | RANK_8)) {
+            problems.push(PositionProblem::PawnOnBackRank {
+                location: location_from_square(square),
+            });
+        }
 
-                if square_colour != &side {
+        for colour in [WHITE, BLACK] {
+            let row = back_row(colour);
+            let king_file = self
+                .get_king_location(colour)
+                .filter(|location| location.0 == row)
+                .map(|location| location.1);
+            let rooks = self.piece_bitboard(ROOK, colour);
+
+            for castle_side in [QUEENSIDE, KINGSIDE] {
+                let Some(rook_file) = self.castling_rook_file(colour, castle_side) else {
                     continue;
+                };
+                // chess960 only needs the rook on the king's side of the right, standard chess
+                // needs both on the squares they started on
+                let standard_rook_file = if castle_side == KINGSIDE { 7 } else { 0 };
+                let rook_is_home = rooks & square_bit((row, rook_file)) != EMPTY_BITBOARD
+                    && king_file.is_some_and(|king_file| {
+                        if self.chess960 {
+                            (castle_side == KINGSIDE) == (rook_file > king_file)
+                        } else {
+                            king_file == 4 && rook_file == standard_rook_file
+                        }
+                    });
+                if !rook_is_home {
+                    problems.push(PositionProblem::CastlingWithoutKingAndRook {
+                        colour,
+                        castle_side,
+                    });
                 }
-                return Some(location);
             }
         }
 
+        if let Some(location) = self.en_passant_location {
+            // the pawn that pushed belongs to the side that just moved, and passed over an empty
+            // square from an empty starting square
+            let pushed_colour = -self.side_to_move;
+            let (pawn_row, passed_row, start_row) = if pushed_colour == WHITE {
+                (4, 5, 6)
+            } else {
+                (3, 2, 1)
+            };
+            let is_possible = location.0 == pawn_row
+                && self.piece_bitboard(PAWN, pushed_colour) & square_bit(location)
+                    != EMPTY_BITBOARD
+                && self.get_piece((passed_row, location.1)) == EMPTY
+                && self.get_piece((start_row, location.1)) == EMPTY;
+            if !is_possible {
+                problems.push(PositionProblem::ImpossibleEnPassant { location });
+            }
+        }
+
+        // checks need both kings on the board
+        let has_kings = !problems
+            .iter()
+            .any(|problem| matches!(problem, PositionProblem::KingCount { .. }));
+        if has_kings && evaluate::is_in_check(self, -self.side_to_move, None) {
+            problems.push(PositionProblem::SideNotToMoveInCheck);
+        }
+
+        if mode == ValidationMode::Strict || problems.iter().any(|problem| !problem.is_repairable())
+        {
+            return if problems.is_empty() {
+                Ok(problems)
+            } else {
+                Err(problems)
+            };
+        }
+
+        for problem in problems.iter() {
+            match *problem {
+                PositionProblem::CastlingWithoutKingAndRook {
+                    colour,
+                    castle_side,
+                } => self.castling_rook_files[colour_index(colour)][castle_side] = None,
+                PositionProblem::ImpossibleEnPassant { .. } => self.en_passant_location = None,
+                _ => {}
+            }
+        }
+        self.zobrist_key = conversion::hash_board_state(self);
+        if let Some(current_position) = self.hash_of_previous_positions.last_mut() {
+            *current_position = self.zobrist_key;
+        }
+        return Ok(problems);
+    }
+
+    /// none while the game can go on. checkmate and stalemate come first, since a mate on the
+    /// move that ends the fifty move count or repeats a position still wins
+    pub fn outcome(&self) -> Option<GameOutcome> {
+        if movegen::generate_legal_moves(self).is_empty() {
+            if evaluate::is_in_check(self, self.side_to_move, None) {
+                return Some(GameOutcome::Checkmate {
+                    winner: -self.side_to_move,
+                });
+            }
+            return Some(GameOutcome::Stalemate);
+        }
+        if self.is_fifty_move_draw() {
+            return Some(GameOutcome::FiftyMoveRule);
+        }
+        if self.has_positions_repeated() {
+            return Some(GameOutcome::ThreefoldRepetition);
+        }
+        if self.has_insufficient_material() {
+            return Some(GameOutcome::InsufficientMaterial);
+        }
         return None;
     }
+
+    /// neither side can ever mate: bare kings, a single minor piece, or only bishops that all
+    /// stand on the same colour squares
+    pub fn has_insufficient_material(&self) -> bool {
+        let heavy_pieces_and_pawns = self.piece_bitboards[piece_index(PAWN)]
+            | self.piece_bitboards[piece_index(ROOK)]
+            | self.piece_bitboards[piece_index(QUEEN)];
+        if heavy_pieces_and_pawns != EMPTY_BITBOARD {
+            return false;
+        }
+
+        let knights = self.piece_bitboards[piece_index(KNIGHT)];
+        let bishops = self.piece_bitboards[piece_index(BISHOP)];
+        if (knights | bishops).count_ones() <= 1 {
+            return true;
+        }
+
+        return knights == EMPTY_BITBOARD
+            && (bishops & LIGHT_SQUARES == EMPTY_BITBOARD
+                || bishops & !LIGHT_SQUARES == EMPTY_BITBOARD);
+    }
+    /// a hundred half moves without a capture or pawn move. a checkmate delivered on the last of
+    /// them still wins, so callers check for that first
+    pub fn is_fifty_move_draw(&self) -> bool {
+        return self.halfmove_clock >= 100;
+    }
+    pub fn get_king_location(&self, side: i8) -> Option<(usize, usize)> {
+        // find king for side
+        let king_bitboard = self.piece_bitboard(KING, side);
+        if king_bitboard == EMPTY_BITBOARD {
+            return None;
+        }
+
+        return Some(location_from_square(king_bitboard.trailing_zeros() as usize));
+    }
+}
+/// the row a colour's pieces start on, and castle along
+pub fn back_row(colour: i8) -> usize {
+    return if colour == WHITE { 7 } else { 0 };
 }
+
+/// where the king and rook finish when castling on that side, the same files as in normal chess
+pub fn castling_targets(row: usize, castle_side: usize) -> ((usize, usize), (usize, usize)) {
+    return if castle_side == KINGSIDE {
+        ((row, 6), (row, 5))
+    } else {
+        ((row, 2), (row, 3))
+    };
+}
+
+/// where the king and rook finish for a castling move, none for any other move.
+/// castling moves go from the king to the rook it castles with
+pub fn castling_destinations(chess_move: Move) -> Option<((usize, usize), (usize, usize))> {
+    let (row, _) = chess_move.from();
+    return match chess_move.flag() {
+        KING_CASTLE => Some(castling_targets(row, KINGSIDE)),
+        QUEEN_CASTLE => Some(castling_targets(row, QUEENSIDE)),
+        _ => None,
+    };
+}
+
 pub fn print_board(board: &Board) {
     let mut row_string = String::new();
 
-    for (row_index, row) in board.colour_array.iter().enumerate() {
-        for (column_index, _colour) in row.iter().enumerate() {
+    for row_index in 0..8 {
+        for column_index in 0..8 {
             let square = board.get_piece((row_index, column_index));
 
             let piece_type = match square {
@@ -599,11 +988,11 @@ pub fn print_board(board: &Board) {
                 _ => " ",
             };
 
-            row_string.push_str("|");
+            row_string.push('|');
             row_string.push_str(piece_type);
         }
         if !row_string.is_empty() {
-            row_string.push_str("|");
+            row_string.push('|');
         }
 
         println!("{}", row_string);
@@ -612,11 +1001,9 @@ pub fn print_board(board: &Board) {
     println!(" ");
 
     // print colour board
-    for (row_index, row) in board.colour_array.iter().enumerate() {
-        // println!("{:?}", row);
-        for (column_index, _colour) in row.iter().enumerate() {
-            // let location = (row_index, column_index);
-            let square = board.colour_array[row_index][column_index];
+    for row_index in 0..8 {
+        for column_index in 0..8 {
+            let square = board.get_piece_colour((row_index, column_index));
 
             let colour = match square {
                 1 => "W",
@@ -625,21 +1012,25 @@ pub fn print_board(board: &Board) {
                 _ => " ",
             };
 
-            row_string.push_str("|");
+            row_string.push('|');
             row_string.push_str(colour);
         }
         if !row_string.is_empty() {
-            row_string.push_str("|");
+            row_string.push('|');
         }
 
         println!("{}", row_string);
         row_string.clear();
     }
     //print all the attributes of the board to the command line
-    println!("can castle on the A1 Rook: {}", board.can_castle_a1);
-    println!("can castle on the H1 Rook: {}", board.can_castle_h1);
-    println!("can castle on the A8 Rook: {}", board.can_castle_a8);
-    println!("can castle on the H8 Rook: {}", board.can_castle_h8);
+    println!(
+        "white castling rook files: {:?}",
+        board.castling_rook_files[colour_index(WHITE)]
+    );
+    println!(
+        "black castling rook files: {:?}",
+        board.castling_rook_files[colour_index(BLACK)]
+    );
 
     println!(
         "en passant plocation {:?}",
@@ -648,6 +1039,8 @@ pub fn print_board(board: &Board) {
     );
 
     println!("game ply: {}", board.ply);
+    println!("halfmove clock: {}", board.halfmove_clock);
+    println!("fen: {}", board.get_fen());
     println!("to move: {}", board.side_to_move);
     println!(
         "is white in check:{}",
@@ -658,3 +1051,314 @@ pub fn print_board(board: &Board) {
         evaluate::is_in_check(board, BLACK, None)
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::board::*;
+
+    fn outcome_of(fen: &str) -> Option<GameOutcome> {
+        return conversion::convert_fen_to_board(fen).outcome();
+    }
+
+    #[test]
+    fn mate_and_stalemate_are_found() {
+        assert_eq!(
+            outcome_of("3R2k1/5ppp/8/8/8/8/8/K7 b - - 0 1"),
+            Some(GameOutcome::Checkmate { winner: WHITE })
+        );
+        assert_eq!(
+            outcome_of("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"),
+            Some(GameOutcome::Stalemate)
+        );
+        assert_eq!(Board::init().outcome(), None);
+    }
+
+    #[test]
+    fn mate_beats_the_fifty_move_rule() {
+        assert_eq!(
+            outcome_of("3R2k1/5ppp/8/8/8/8/8/K7 b - - 100 80"),
+            Some(GameOutcome::Checkmate { winner: WHITE })
+        );
+        assert_eq!(
+            outcome_of("7k/5ppp/8/8/8/8/8/K2R4 b - - 100 80"),
+            Some(GameOutcome::FiftyMoveRule)
+        );
+    }
+
+    #[test]
+    fn insufficient_material() {
+        assert_eq!(
+            outcome_of("8/8/3k4/8/8/3K4/8/8 w - - 0 1"),
+            Some(GameOutcome::InsufficientMaterial)
+        );
+        assert_eq!(
+            outcome_of("8/8/3k4/8/8/3K4/5N2/8 w - - 0 1"),
+            Some(GameOutcome::InsufficientMaterial)
+        );
+        // both bishops on dark squares
+        assert_eq!(
+            outcome_of("8/2b5/3k4/8/8/3K4/8/6B1 w - - 0 1"),
+            Some(GameOutcome::InsufficientMaterial)
+        );
+        assert_eq!(outcome_of("8/1b6/3k4/8/8/3K4/8/6B1 w - - 0 1"), None);
+        assert_eq!(outcome_of("8/8/3k4/8/8/3K4/5NN1/8 w - - 0 1"), None);
+    }
+
+    #[test]
+    fn strict_validation_reports_every_problem() {
+        let mut board = conversion::convert_fen_to_board("P3k3/8/8/8/8/8/8/2K1K2R w - - 0 1");
+        let problems = board.validate(ValidationMode::Strict).unwrap_err();
+        assert_eq!(
+            problems,
+            vec![
+                PositionProblem::KingCount {
+                    colour: WHITE,
+                    count: 2
+                },
+                PositionProblem::PawnOnBackRank { location: (0, 0) },
+            ]
+        );
+
+        let mut board = conversion::convert_fen_to_board("4k3/8/8/8/8/8/8/4R1K1 w - - 0 1");
+        assert_eq!(
+            board.validate(ValidationMode::Strict).unwrap_err(),
+            vec![PositionProblem::SideNotToMoveInCheck]
+        );
+        assert!(Board::init().validate(ValidationMode::Strict).is_ok());
+    }
+
+    #[test]
+    fn lenient_validation_drops_bad_castling_and_en_passant() {
+        let mut board = conversion::convert_fen_to_board("r3k3/8/8/8/4P3/8/8/4K2R b KQkq e3 0 1");
+        let repaired = board.validate(ValidationMode::Lenient).unwrap();
+        assert_eq!(repaired.len(), 2);
+        assert_eq!(board.get_fen(), "r3k3/8/8/8/4P3/8/8/4K2R b Kq e3 0 1");
+        assert_eq!(board.zobrist_key, conversion::hash_board_state(&board));
+
+        let mut board = conversion::convert_fen_to_board("4k3/8/8/8/8/4P3/8/4K3 b - e3 0 1");
+        board.validate(ValidationMode::Lenient).unwrap();
+        assert_eq!(board.get_fen(), "4k3/8/8/8/8/4P3/8/4K3 b - - 0 1");
+    }
+
+    #[test]
+    fn standard_castling_needs_the_king_on_the_e_file() {
+        let fen = "4k3/8/8/8/8/8/8/R2K3R w KQ - 0 1";
+        let mut board = conversion::convert_fen_to_board(fen);
+        assert_eq!(
+            board.validate(ValidationMode::Strict).unwrap_err(),
+            vec![
+                PositionProblem::CastlingWithoutKingAndRook {
+                    colour: WHITE,
+                    castle_side: QUEENSIDE
+                },
+                PositionProblem::CastlingWithoutKingAndRook {
+                    colour: WHITE,
+                    castle_side: KINGSIDE
+                },
+            ]
+        );
+
+        let mut board = conversion::convert_fen_to_board(fen);
+        assert_eq!(board.validate(ValidationMode::Lenient).unwrap().len(), 2);
+        assert_eq!(board.get_fen(), "4k3/8/8/8/8/8/8/R2K3R w - - 0 1");
+
+        // the same rights are fine in chess960
+        let mut board = conversion::convert_fen_to_board(fen);
+        board.chess960 = true;
+        assert!(board.validate(ValidationMode::Strict).is_ok());
+    }
+
+    #[test]
+    fn move_input_is_checked_against_the_legal_moves() {
+        let mut board = conversion::convert_fen_to_board("r3k3/1P6/8/8/8/8/4P3/R3K2R w KQq - 0 1");
+
+        assert_eq!(
+            board.make_move_with_notation("e2e5".to_string()),
+            Err(MoveParseError::Illegal("e2e5".to_string()))
+        );
+        assert_eq!(
+            board.make_move_with_notation("a8a1".to_string()),
+            Err(MoveParseError::Illegal("a8a1".to_string()))
+        );
+        assert_eq!(
+            board.parse_move("e2"),
+            Err(MoveParseError::Malformed("e2".to_string()))
+        );
+        assert_eq!(
+            board.parse_move("b7a8"),
+            Err(MoveParseError::Ambiguous("b7a8".to_string()))
+        );
+        assert!(board.parse_move("b7a8n").unwrap().is_promotion());
+
+        // both ways of writing castling are understood outside of chess960
+        assert_eq!(board.parse_move("e1g1"), board.parse_move("e1h1"));
+        assert!(board.parse_move("e1c1").unwrap().is_castle());
+        assert_eq!(board.move_list.len(), 0);
+    }
+
+    #[test]
+    fn null_move_passes_the_turn_and_comes_back() {
+        let mut board = conversion::convert_fen_to_board(
+            "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3",
+        );
+        let fen = board.get_fen();
+        let key = board.zobrist_key;
+
+        board.make_null_move();
+        assert_eq!(
+            board.get_fen(),
+            "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR w KQkq - 1 4"
+        );
+        assert_eq!(board.zobrist_key, conversion::hash_board_state(&board));
+
+        board.un_make_null_move();
+        assert_eq!(board.get_fen(), fen);
+        assert_eq!(board.zobrist_key, key);
+        assert!(board.move_list.is_empty());
+    }
+
+    #[test]
+    fn repetitions_do_not_count_across_a_null_move() {
+        let mut board = Board::init();
+        for _ in 0..2 {
+            for notation in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+                board.make_move_with_notation(notation.to_string()).unwrap();
+            }
+        }
+        board.make_null_move();
+        board.make_null_move();
+        assert_eq!(board.zobrist_key, Board::init().zobrist_key);
+        assert!(!board.has_positions_repeated());
+
+        board.un_make_null_move();
+        board.un_make_null_move();
+        board.make_move_with_notation("g1f3".to_string()).unwrap();
+        assert!(board.has_positions_repeated());
+    }
+
+    #[test]
+    fn threefold_repetition() {
+        let mut board = Board::init();
+        for _ in 0..2 {
+            for notation in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+                assert_eq!(board.outcome(), None);
+                board.make_move_with_notation(notation.to_string()).unwrap();
+            }
+        }
+        board.make_move_with_notation("g1f3".to_string()).unwrap();
+        assert_eq!(board.outcome(), Some(GameOutcome::ThreefoldRepetition));
+    }
+
+    #[test]
+    fn bitboards_match_the_fen() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        ];
+        for fen in fens {
+            let board = Board::from_fen(fen).unwrap();
+
+            // the placement field spelled out one character per square, a8 first
+            let squares: Vec<char> = fen
+                .split(' ')
+                .next()
+                .unwrap()
+                .chars()
+                .filter(|&c| c != '/')
+                .flat_map(|c| match c.to_digit(10) {
+                    Some(empty) => vec!['.'; empty as usize],
+                    None => vec![c],
+                })
+                .collect();
+            assert_eq!(squares.len(), 64);
+
+            for (square, &letter) in squares.iter().enumerate() {
+                let location = location_from_square(square);
+                let bit = 1u64 << square;
+                let expected = match letter.to_ascii_lowercase() {
+                    'p' => PAWN,
+                    'n' => KNIGHT,
+                    'b' => BISHOP,
+                    'r' => ROOK,
+                    'q' => QUEEN,
+                    'k' => KING,
+                    _ => EMPTY,
+                };
+                assert_eq!(board.get_piece(location), expected, "{} {}", fen, square);
+                if expected == EMPTY {
+                    assert_eq!(board.occupied() & bit, EMPTY_BITBOARD);
+                    continue;
+                }
+
+                let colour = if letter.is_ascii_uppercase() {
+                    WHITE
+                } else {
+                    BLACK
+                };
+                assert_eq!(board.get_piece_colour(location), colour);
+                assert_ne!(board.piece_bitboard(expected, colour) & bit, EMPTY_BITBOARD);
+                assert_eq!(board.colour_bitboard(-colour) & bit, EMPTY_BITBOARD);
+            }
+
+            // every piece is on exactly one piece bitboard
+            let piece_total: u32 = board.piece_bitboards.iter().map(|b| b.count_ones()).sum();
+            assert_eq!(piece_total, board.occupied().count_ones());
+            assert_eq!(board.colour_bitboards[0] & board.colour_bitboards[1], 0);
+        }
+    }
+
+    #[test]
+    fn the_starting_position_counts_towards_repetition() {
+        let fen_board = Board::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        for (mut board, cycle) in [
+            (Board::init(), ["g1f3", "g8f6", "f3g1", "f6g8"]),
+            (fen_board, ["a1a2", "e8d8", "a2a1", "d8e8"]),
+        ] {
+            for notation in cycle.iter().chain(cycle.iter()) {
+                assert_eq!(board.outcome(), None);
+                board.make_move_with_notation(notation.to_string()).unwrap();
+            }
+            assert_eq!(board.outcome(), Some(GameOutcome::ThreefoldRepetition));
+        }
+    }
+
+    #[test]
+    fn attack_queries_agree_with_each_other() {
+        for fen in crate::constants::BENCH_FENS.iter() {
+            let board = Board::from_fen(fen).unwrap();
+            for colour in [WHITE, BLACK] {
+                let attacked_squares = (0..64)
+                    .filter(|&square| board.attackers_to(square, colour) != EMPTY_BITBOARD)
+                    .fold(EMPTY_BITBOARD, |map, square| map | (1u64 << square));
+                assert_eq!(board.attack_map(colour), attacked_squares, "{}", fen);
+            }
+            assert_eq!(
+                board.checkers() != EMPTY_BITBOARD,
+                evaluate::is_in_check(&board, board.side_to_move, None),
+                "{}",
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn checkers_and_pins() {
+        // the rook on e8 checks, the bishop on b4 pins the knight and the queen on h5 pins nothing
+        let board = Board::from_fen("4r1k1/8/8/7q/1b6/8/3N4/4K3 w - - 0 1").unwrap();
+        let e8 = square_from_location((0, 4));
+        let d2 = square_from_location((6, 3));
+        let e1 = square_from_location((7, 4));
+
+        assert_eq!(board.checkers(), 1u64 << e8);
+        assert_eq!(board.pinned_pieces(WHITE), 1u64 << d2);
+        assert_eq!(board.pinned_pieces(BLACK), EMPTY_BITBOARD);
+        let b4 = square_from_location((4, 1));
+        let h5 = square_from_location((3, 7));
+        assert_eq!(board.all_attackers_to(d2), (1u64 << e1) | (1u64 << b4));
+        assert_eq!(
+            board.all_attackers_to(e1 - 8),
+            (1u64 << e1) | (1u64 << e8) | (1u64 << h5)
+        );
+    }
+}
@@ -6,10 +6,16 @@ pub const ROOK: i8 = 4;
 pub const QUEEN: i8 = 5;
 pub const KING: i8 = 6;
 
+pub const CHECKMATE_SCORE: i32 = 100_000;
+
 pub const WHITE: i8 = 1;
 pub const BLACK: i8 = -1;
 pub const EMPTY: i8 = 0;
 
+// the two ways to castle, used to index castling rights
+pub const QUEENSIDE: usize = 0;
+pub const KINGSIDE: usize = 1;
+
 pub const BOARD_COORDINATES: [[&str; 8]; 8] = [
     ["a8", "b8", "c8", "d8", "e8", "f8", "g8", "h8"],
     ["a7", "b7", "c7", "d7", "e7", "f7", "g7", "h7"],
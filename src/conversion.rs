@@ -1,148 +1,268 @@
 // use crate::board;
+use crate::bitboard::*;
+use crate::board;
 use crate::board::Board;
 use crate::constants;
 use crate::constants::BLACK;
 use crate::constants::WHITE;
-use std::collections::hash_map::DefaultHasher;
-use std::hash::Hasher;
+use crate::zobrist;
 
 use crate::moves::*;
 // use crate::evaluate;
-pub fn convert_fen_to_board(fen: &str) -> Board {
-    // implementation here
+use std::fmt;
+
+/// what is wrong with a fen that can't be read
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FenError {
+    /// the fen stops before the named field
+    MissingField(&'static str),
+    /// the placement doesn't have eight ranks separated by slashes
+    WrongNumberOfRanks(usize),
+    /// a rank, numbered 8 down to 1, that doesn't cover eight squares
+    BadRankLength {
+        rank: usize,
+        squares: usize,
+    },
+    UnknownPiece(char),
+    BadSideToMove(String),
+    BadCastling(String),
+    BadEnPassant(String),
+    BadClock(String),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FenError::MissingField(field) => write!(f, "missing {}", field),
+            FenError::WrongNumberOfRanks(ranks) => write!(f, "{} ranks instead of 8", ranks),
+            FenError::BadRankLength { rank, squares } => {
+                write!(f, "rank {} covers {} squares instead of 8", rank, squares)
+            }
+            FenError::UnknownPiece(character) => write!(f, "unknown piece '{}'", character),
+            FenError::BadSideToMove(side) => write!(f, "bad side to move '{}'", side),
+            FenError::BadCastling(castling) => write!(f, "bad castling rights '{}'", castling),
+            FenError::BadEnPassant(square) => write!(f, "bad en passant square '{}'", square),
+            FenError::BadClock(clock) => write!(f, "bad move clock '{}'", clock),
+        }
+    }
+}
 
-    // split by whitespace
+/// build a board from a fen that is known to be good, like the bench positions. anything typed
+/// in or sent over uci should go through Board::from_fen instead
+pub fn convert_fen_to_board(fen: &str) -> Board {
+    return parse_fen(fen).unwrap_or_else(|error| panic!("invalid fen {}: {}", fen, error));
+}
 
+/// read a fen. the placement and side to move are needed, the other fields default to no
+/// castling, no en passant square and the clocks of a new game
+pub fn parse_fen(fen: &str) -> Result<Board, FenError> {
     let mut board = Board::init();
 
     board.clear_board();
 
-    board.can_castle_a1 = false;
-    board.can_castle_a8 = false;
-    board.can_castle_h1 = false;
-    board.can_castle_h8 = false;
-
-    // board is 12 x 12, but fen is 8x8. Need to convert
-    // board starts at 2,2 to 2,10
-    // and 10,2 and 10,10
-    for (index, section) in fen.split_whitespace().enumerate() {
-        match index {
-            0 => {
-                let mut current_row = 0;
-                for characters in section.split('/') {
-                    let mut current_column = 0;
-                    for character in characters.chars() {
-                        if current_column > 7 {
-                            break;
-                        }
-
-                        if character.is_alphabetic() {
-                            let piece_colour = if character.is_uppercase() {
-                                WHITE
-                            } else {
-                                BLACK
-                            };
-
-                            board.set_piece_and_colour(
-                                (current_row, current_column),
-                                convert_alphabetic_to_piece(character),
-                                piece_colour,
-                            );
-                            current_column += 1;
-                        }
-
-                        if character.is_numeric() {
-                            current_column += character.to_digit(10).unwrap() as usize;
-                        }
-                    }
-
-                    current_row += 1;
-                    if current_row > 7 {
-                        break;
-                    }
-                }
+    board.castling_rook_files = [[None; 2]; 2];
+
+    let mut sections = fen.split_whitespace();
+
+    let placement = sections
+        .next()
+        .ok_or(FenError::MissingField("piece placement"))?;
+    let ranks: Vec<&str> = placement.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(FenError::WrongNumberOfRanks(ranks.len()));
+    }
+    for (current_row, characters) in ranks.iter().enumerate() {
+        let mut current_column = 0;
+        for character in characters.chars() {
+            if let Some(empty_squares @ 1..=8) = character.to_digit(10) {
+                current_column += empty_squares as usize;
+                continue;
             }
 
-            1 => {
-                // side to move
-                match section {
-                    "w" => board.side_to_move = WHITE,
-                    "b" => board.side_to_move = BLACK,
-                    "-" => {}
-                    _ => todo!(), // probably panic
-                }
+            let piece = convert_alphabetic_to_piece(character);
+            if piece < 0 || !character.is_ascii_alphabetic() {
+                return Err(FenError::UnknownPiece(character));
+            }
+            if current_column < 8 {
+                let piece_colour = if character.is_uppercase() {
+                    WHITE
+                } else {
+                    BLACK
+                };
+                board.set_piece_and_colour((current_row, current_column), piece, piece_colour);
             }
-            2 => {
-                for character in section.chars() {
-                    match character {
-                        'k' => board.can_castle_h8 = true,
-                        'q' => board.can_castle_a8 = true,
-                        'K' => board.can_castle_h1 = true,
-                        'Q' => board.can_castle_a1 = true,
-                        '-' => {}
-                        _ => todo!(),
-                    }
-                }
+            current_column += 1;
+        }
+
+        if current_column != 8 {
+            return Err(FenError::BadRankLength {
+                rank: 8 - current_row,
+                squares: current_column,
+            });
+        }
+    }
+
+    // side to move
+    match sections.next() {
+        Some("w") => board.side_to_move = WHITE,
+        Some("b") => board.side_to_move = BLACK,
+        Some(side) => return Err(FenError::BadSideToMove(side.to_string())),
+        None => return Err(FenError::MissingField("side to move")),
+    }
+
+    let castling = sections.next().unwrap_or("-");
+    if castling != "-" {
+        let is_castling_character =
+            |character: char| matches!(character, 'K' | 'Q' | 'k' | 'q' | 'A'..='H' | 'a'..='h');
+        let has_repeats = castling
+            .chars()
+            .enumerate()
+            .any(|(index, character)| castling[..index].contains(character));
+        if castling.len() > 4 || has_repeats || !castling.chars().all(is_castling_character) {
+            return Err(FenError::BadCastling(castling.to_string()));
+        }
+        for character in castling.chars() {
+            if !add_castling_right(&mut board, character) {
+                return Err(FenError::BadCastling(castling.to_string()));
             }
-            3 => {
-                let en_passant_column = section.chars().nth(0).unwrap();
-                if en_passant_column == '-' {
-                    continue;
-                }
-                // board.en_passant = true;
-                let en_passant_row = section.chars().nth(1).unwrap();
-                match en_passant_row {
-                    '3' => {
-                        for (board_row_index, board_row) in
-                            constants::BOARD_COORDINATES.iter().enumerate()
-                        {
-                            for (column_index, square_coordinate) in board_row.iter().enumerate() {
-                                if *square_coordinate
-                                    == format!(
-                                        "{}{}",
-                                        en_passant_column,
-                                        (en_passant_row.to_digit(10).unwrap() + 1)
-                                    )
-                                {
-                                    board.en_passant_location =
-                                        Some((board_row_index, column_index));
-
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                    '6' => {
-                        for (board_row_index, board_row) in
-                            constants::BOARD_COORDINATES.iter().enumerate()
-                        {
-                            for (column_index, square_coordinate) in board_row.iter().enumerate() {
-                                if *square_coordinate
-                                    == format!(
-                                        "{}{}",
-                                        (en_passant_row.to_digit(10).unwrap() - 1),
-                                        en_passant_column
-                                    )
-                                {
-                                    board.en_passant_location =
-                                        Some((board_row_index, column_index));
-
-                                    break;
-                                }
-                            }
-                            break;
-                        }
-                    }
-                    _ => todo!(),
-                }
-            } // en passant
-            4 => {} // halfmove, i havent done this
-            5 => board.ply = section.parse::<i32>().unwrap(),
-            _ => {}
         }
     }
 
-    return board;
+    let en_passant = sections.next().unwrap_or("-");
+    if en_passant != "-" {
+        // fen gives the square behind the pawn, but the board stores the pawn itself,
+        // which is one rank further from its own side. the pawn belongs to the side that just moved
+        let pawn_row = match en_passant.as_bytes() {
+            [b'a'..=b'h', b'6'] if board.side_to_move == WHITE => 3,
+            [b'a'..=b'h', b'3'] if board.side_to_move == BLACK => 4,
+            _ => return Err(FenError::BadEnPassant(en_passant.to_string())),
+        };
+        let column = (en_passant.as_bytes()[0] - b'a') as usize;
+        board.en_passant_location = Some((pawn_row, column));
+    }
+
+    if let Some(halfmove) = sections.next() {
+        board.halfmove_clock = halfmove
+            .parse::<u32>()
+            .map_err(|_| FenError::BadClock(halfmove.to_string()))?;
+    }
+
+    // the fullmove number starts at 1 and goes up after black moves
+    let fullmove = match sections.next() {
+        Some(fullmove) => fullmove
+            .parse::<i32>()
+            .map_err(|_| FenError::BadClock(fullmove.to_string()))?
+            .max(1),
+        None => 1,
+    };
+    board.ply = (fullmove - 1) * 2 + if board.side_to_move == BLACK { 1 } else { 0 };
+
+    board.zobrist_key = hash_board_state(&board);
+    board.start_position_history();
+
+    return Ok(board);
+}
+
+/// the fen for chess960 start position `index`, from 0 to 959, numbered the usual way so that
+/// 518 is the normal chess start position
+pub fn chess960_start_fen(index: usize) -> String {
+    let mut back_rank = [' '; 8];
+    let mut remaining = index % 960;
+
+    // the bishops go on opposite colours, the light squared one first
+    back_rank[(remaining % 4) * 2 + 1] = 'b';
+    remaining /= 4;
+    back_rank[(remaining % 4) * 2] = 'b';
+    remaining /= 4;
+
+    // the rest are placed counting only the files still empty
+    let mut place_on_nth_empty_file = |piece: char, nth: usize| {
+        let file = (0..8)
+            .filter(|&file| back_rank[file] == ' ')
+            .nth(nth)
+            .unwrap();
+        back_rank[file] = piece;
+    };
+
+    place_on_nth_empty_file('q', remaining % 6);
+    remaining /= 6;
+
+    // the second knight is placed after the first, so one fewer empty file is counted
+    let knight_files = [
+        (0, 1),
+        (0, 2),
+        (0, 3),
+        (0, 4),
+        (1, 2),
+        (1, 3),
+        (1, 4),
+        (2, 3),
+        (2, 4),
+        (3, 4),
+    ];
+    let (first_knight, second_knight) = knight_files[remaining];
+    place_on_nth_empty_file('n', first_knight);
+    place_on_nth_empty_file('n', second_knight - 1);
+
+    // the king always ends up between the rooks
+    place_on_nth_empty_file('r', 0);
+    place_on_nth_empty_file('k', 0);
+    place_on_nth_empty_file('r', 0);
+
+    let black_pieces: String = back_rank.iter().collect();
+    return format!(
+        "{}/pppppppp/8/8/8/8/PPPPPPPP/{} w KQkq - 0 1",
+        black_pieces,
+        black_pieces.to_uppercase()
+    );
+}
+
+/// read one castling character. KQkq (X-FEN) mean the outermost rook on that side of the king,
+/// a file letter (Shredder-FEN) names the rook's file, which chess960 needs when the
+/// outermost rook isn't the one that can castle. false if the side already had a right there,
+/// as in KH
+fn add_castling_right(board: &mut Board, character: char) -> bool {
+    let colour = if character.is_uppercase() {
+        WHITE
+    } else {
+        BLACK
+    };
+    let back_row = board::back_row(colour);
+    let king_file = board
+        .get_king_location(colour)
+        .map_or(4, |location| location.1);
+    let rooks = board.piece_bitboard(constants::ROOK, colour);
+    let has_rook_on = |file: usize| rooks & square_bit((back_row, file)) != EMPTY_BITBOARD;
+
+    let (castle_side, rook_file) = match character.to_ascii_lowercase() {
+        'k' => (
+            constants::KINGSIDE,
+            (king_file + 1..8)
+                .rev()
+                .find(|&file| has_rook_on(file))
+                .unwrap_or(7),
+        ),
+        'q' => (
+            constants::QUEENSIDE,
+            (0..king_file).find(|&file| has_rook_on(file)).unwrap_or(0),
+        ),
+        file_letter => {
+            let file = (file_letter as u8 - b'a') as usize;
+            let castle_side = if file > king_file {
+                constants::KINGSIDE
+            } else {
+                constants::QUEENSIDE
+            };
+            (castle_side, file)
+        }
+    };
+
+    let castling_right = &mut board.castling_rook_files[colour_index(colour)][castle_side];
+    if castling_right.is_some() {
+        return false;
+    }
+    *castling_right = Some(rook_file);
+    return true;
 }
 
 pub fn get_piece_square_value_mg(location: (usize, usize), piece_type: i8, colour: i8) -> i32 {
@@ -202,18 +322,38 @@ pub fn convert_alphabetic_to_piece(character: char) -> i8 {
         _ => -1,
     }
 }
+pub fn convert_piece_to_alphabetic(piece: i8) -> char {
+    match piece {
+        constants::KING => 'k',
+        constants::QUEEN => 'q',
+        constants::ROOK => 'r',
+        constants::BISHOP => 'b',
+        constants::KNIGHT => 'n',
+        _ => 'p',
+    }
+}
+/// the move in uci notation, with castling written as the king moving two squares
 pub fn convert_move_to_notation(chess_move: &Move) -> String {
-    let promotion = Some(match chess_move.promotion_to.unwrap_or_default() {
-        1 => 'p'.to_string(),
-        2 => 'n'.to_string(),
-        3 => 'b'.to_string(),
-        4 => 'r'.to_string(),
-        5 => 'q'.to_string(),
-        6 => 'k'.to_string(),
-        0 => ' '.to_string(),
-        _ => ' '.to_string(),
+    return convert_move_to_uci_notation(chess_move, false);
+}
+/// the move in uci notation. chess960 castling is written as the king taking its own rook,
+/// otherwise as the king moving two squares
+pub fn convert_move_to_uci_notation(chess_move: &Move, chess960: bool) -> String {
+    let promotion = chess_move.promotion_to().map(|piece| {
+        match piece {
+            constants::KNIGHT => 'n',
+            constants::BISHOP => 'b',
+            constants::ROOK => 'r',
+            _ => 'q',
+        }
+        .to_string()
     });
-    return convert_array_location_to_notation(chess_move.from, chess_move.to, promotion);
+
+    let to = match board::castling_destinations(*chess_move) {
+        Some((king_to, _)) if !chess960 => king_to,
+        _ => chess_move.to(),
+    };
+    return convert_array_location_to_notation(chess_move.from(), to, promotion);
 }
 pub fn convert_array_location_to_notation(
     from: (usize, usize),
@@ -227,9 +367,9 @@ pub fn convert_array_location_to_notation(
     notation_move.push_str(start_location);
     notation_move.push_str(end_location);
 
-    if promotion.is_some() {
+    if let Some(promotion) = promotion {
         // println!("promoted {}", &promotion.clone().unwrap());
-        notation_move.push_str(&promotion.unwrap().clone())
+        notation_move.push_str(&promotion)
     }
     return notation_move;
 }
@@ -253,56 +393,289 @@ pub fn normalise_score_to_cp(score: i32) -> f64 {
     return (2.00 * (1.00 / (1.00 + (-score as f64 / 1000.00).exp())) - 1.00).clamp(-1.00, 1.00);
 }
 /// convert current board state into fen
-pub fn convert_board_to_fen(_board: &Board) -> String {
-    let fen_string = String::new();
+pub fn convert_board_to_fen(board: &Board) -> String {
+    let mut fen_string = String::new();
 
     // loop over each rank, adding to fen string
+    for row in 0..8 {
+        let mut empty_squares = 0;
+        for column in 0..8 {
+            let piece = board.get_piece((row, column));
+            if piece == constants::EMPTY {
+                empty_squares += 1;
+                continue;
+            }
+            if empty_squares > 0 {
+                fen_string.push_str(&empty_squares.to_string());
+                empty_squares = 0;
+            }
+            let character = convert_piece_to_alphabetic(piece);
+            if board.get_piece_colour((row, column)) == WHITE {
+                fen_string.push(character.to_ascii_uppercase());
+            } else {
+                fen_string.push(character);
+            }
+        }
+        if empty_squares > 0 {
+            fen_string.push_str(&empty_squares.to_string());
+        }
+        if row < 7 {
+            fen_string.push('/');
+        }
+    }
 
     // then add the color (w / b) whose turn it is
+    fen_string.push_str(if board.side_to_move == WHITE {
+        " w "
+    } else {
+        " b "
+    });
 
     // then add the castling rights (KQkq)
+    let castling_rights = castling_rights_to_fen(board);
+    fen_string.push_str(if castling_rights.is_empty() {
+        "-"
+    } else {
+        &castling_rights
+    });
 
     // then add the en passant square (e3) - the square behind the pawn
+    fen_string.push(' ');
+    match board.en_passant_location {
+        Some((pawn_row, column)) => {
+            // the board keeps the pawn that moved, one rank further on than the square it passed
+            let behind_row = if pawn_row == 4 { 5 } else { 2 };
+            fen_string.push_str(constants::BOARD_COORDINATES[behind_row][column]);
+        }
+        None => fen_string.push('-'),
+    }
 
-    // then add the halfmove clock (h3) - how many halfmoves since the last capture or pawn advancement
+    // then add the halfmove clock and the fullmove number, which starts at 1
+    fen_string.push_str(&format!(" {} {}", board.halfmove_clock, board.ply / 2 + 1));
 
     return fen_string;
 }
 
-pub fn hash_board_state(board: &Board) -> u64 {
-    // take board state and generate a hash to use to compare uniqueness of position
+/// KQkq when the castling rook is the outermost rook on its side of the king, which is always
+/// the case in normal chess. otherwise the rook's file, as shredder fen does
+fn castling_rights_to_fen(board: &Board) -> String {
+    let mut castling_rights = String::new();
+
+    for colour in [WHITE, BLACK] {
+        let back_row = board::back_row(colour);
+        let rooks = board.piece_bitboard(constants::ROOK, colour);
+        let has_rook_on = |file: usize| rooks & square_bit((back_row, file)) != EMPTY_BITBOARD;
+
+        for castle_side in [constants::KINGSIDE, constants::QUEENSIDE] {
+            let Some(rook_file) = board.castling_rook_file(colour, castle_side) else {
+                continue;
+            };
+            let rook_further_out = if castle_side == constants::KINGSIDE {
+                (rook_file + 1..8).any(has_rook_on)
+            } else {
+                (0..rook_file).any(has_rook_on)
+            };
 
-    let mut hasher = DefaultHasher::new();
-    for row in board.board_array.iter() {
-        for square in row.iter() {
-            hasher.write_i8(*square);
+            let character = match (rook_further_out, castle_side) {
+                (true, _) => (b'a' + rook_file as u8) as char,
+                (false, constants::KINGSIDE) => 'k',
+                (false, _) => 'q',
+            };
+            if colour == WHITE {
+                castling_rights.push(character.to_ascii_uppercase());
+            } else {
+                castling_rights.push(character);
+            }
         }
     }
-    for row in board.colour_array.iter() {
-        for square in row.iter() {
-            hasher.write_i8(*square);
+
+    return castling_rights;
+}
+
+/// work out the zobrist hash of the board from scratch.
+/// the board keeps its own key up to date as moves are made, this is for setting it up and checking it
+pub fn hash_board_state(board: &Board) -> u64 {
+    let mut hash = 0;
+
+    for colour in [constants::WHITE, constants::BLACK] {
+        for piece in constants::PAWN..=constants::KING {
+            for square in Squares(board.piece_bitboard(piece, colour)) {
+                hash ^= zobrist::piece_key(piece, colour, location_from_square(square));
+            }
         }
     }
 
-    hasher.write_i8(board.side_to_move);
+    if board.side_to_move == BLACK {
+        hash ^= zobrist::ZOBRIST_KEYS.black_to_move;
+    }
 
-    // return format!("{:x}", hasher.finish());
-    return hasher.finish();
+    return hash ^ zobrist::castling_key(board) ^ zobrist::en_passant_key(board);
 }
-pub fn hash_board_state_for_tt(board: &Board) -> u64 {
-    // take board state and generate a hash to use to compare uniqueness of position
 
-    let mut hasher = DefaultHasher::new();
-    for row in board.board_array.iter() {
-        for square in row.iter() {
-            hasher.write_i8(*square);
+#[cfg(test)]
+mod tests {
+    use crate::constants::*;
+    use crate::conversion::*;
+
+    #[test]
+    fn chess960_start_positions_by_index() {
+        assert_eq!(
+            chess960_start_fen(518),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+        );
+        assert!(chess960_start_fen(0).starts_with("bbqnnrkr/"));
+        assert!(chess960_start_fen(959).starts_with("rkrnnqbb/"));
+
+        let mut back_ranks: Vec<String> = (0..960)
+            .map(|index| {
+                chess960_start_fen(index)
+                    .split('/')
+                    .next()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        for back_rank in back_ranks.iter() {
+            let king = back_rank.find('k').unwrap();
+            assert!(back_rank.find('r').unwrap() < king && back_rank.rfind('r').unwrap() > king);
+            let bishops: Vec<usize> = back_rank.match_indices('b').map(|(file, _)| file).collect();
+            assert_ne!(bishops[0] % 2, bishops[1] % 2);
         }
+        back_ranks.sort();
+        back_ranks.dedup();
+        assert_eq!(back_ranks.len(), 960);
+    }
+
+    #[test]
+    fn castling_rights_read_as_rook_files() {
+        // shredder-fen names the files
+        let board = convert_fen_to_board("1r2k1r1/8/8/8/8/8/8/R3K2R w HAg - 0 1");
+        assert_eq!(board.castling_rook_files[0], [Some(0), Some(7)]);
+        assert_eq!(board.castling_rook_files[1], [None, Some(6)]);
+
+        // x-fen letters mean the outermost rook on that side
+        let board = convert_fen_to_board("rr2k1r1/8/8/8/8/8/8/1R2K1RR w KQkq - 0 1");
+        assert_eq!(board.castling_rook_files[0], [Some(1), Some(7)]);
+        assert_eq!(board.castling_rook_files[1], [Some(0), Some(6)]);
+    }
+
+    #[test]
+    fn chess960_castling_is_written_king_takes_rook() {
+        let mut board = convert_fen_to_board("4k3/8/8/8/8/8/8/1R2KR2 w FB - 0 1");
+        board.chess960 = true;
+
+        let castle = board.convert_notation_to_move("e1b1".to_string()).unwrap();
+        assert!(castle.is_castle());
+        assert!(crate::movegen::generate_legal_moves(&board).contains(castle));
+        assert_eq!(convert_move_to_uci_notation(&castle, true), "e1b1");
+        assert_eq!(convert_move_to_notation(&castle), "e1c1");
+
+        board.make_move(castle);
+        assert_eq!(board.get_piece((7, 2)), KING);
+        assert_eq!(board.get_piece((7, 3)), ROOK);
+        assert_eq!(board.get_piece((7, 1)), EMPTY);
+        assert_eq!(board.castling_rook_files[0], [None, None]);
+
+        board.un_make_move(castle);
+        assert_eq!(board.get_piece((7, 4)), KING);
+        assert_eq!(board.get_piece((7, 1)), ROOK);
+        assert_eq!(board.castling_rook_files[0], [Some(1), Some(5)]);
     }
-    for row in board.colour_array.iter() {
-        for square in row.iter() {
-            hasher.write_i8(*square);
+
+    #[test]
+    fn halfmove_clock_follows_make_and_unmake() {
+        let mut board = convert_fen_to_board("4k3/8/8/8/8/8/4P3/R3K3 b Q - 12 40");
+        assert_eq!(board.halfmove_clock, 12);
+        assert_eq!(board.ply, 79);
+
+        let king_move = board.make_move_with_notation("e8d8".to_string()).unwrap();
+        assert_eq!(board.halfmove_clock, 13);
+        let pawn_move = board.make_move_with_notation("e2e4".to_string()).unwrap();
+        assert_eq!(board.halfmove_clock, 0);
+
+        board.un_make_move(pawn_move);
+        assert_eq!(board.halfmove_clock, 13);
+        board.un_make_move(king_move);
+        assert_eq!(board.halfmove_clock, 12);
+    }
+
+    #[test]
+    fn fen_round_trips_through_the_board() {
+        for fen in BENCH_FENS {
+            assert_eq!(convert_board_to_fen(&convert_fen_to_board(fen)), fen);
         }
+        assert_eq!(
+            Board::init().get_fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+        );
     }
 
-    return hasher.finish();
+    #[test]
+    fn fen_keeps_chess960_rook_files_and_en_passant() {
+        // the g1 rook needs its file because the h1 rook is further out, the rest are outermost
+        let board = convert_fen_to_board("rr2k1r1/8/8/8/8/8/8/1R2K1RR w GBga - 0 1");
+        assert_eq!(board.get_fen(), "rr2k1r1/8/8/8/8/8/8/1R2K1RR w GQkq - 0 1");
+
+        let mut board = Board::init();
+        board.make_move_with_notation("e2e4".to_string()).unwrap();
+        assert_eq!(
+            board.get_fen(),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+        );
+    }
+
+    #[test]
+    fn bad_fens_are_rejected_with_the_reason() {
+        let error_for = |fen: &str| parse_fen(fen).err();
+
+        assert_eq!(
+            error_for("rnbqkbnr/ppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            Some(FenError::BadRankLength {
+                rank: 7,
+                squares: 7
+            })
+        );
+        assert_eq!(
+            error_for("rnbqkbnr/pppppppp/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+            Some(FenError::WrongNumberOfRanks(7))
+        );
+        assert_eq!(
+            error_for("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNX w KQkq - 0 1"),
+            Some(FenError::UnknownPiece('X'))
+        );
+        assert_eq!(
+            error_for("4k3/8/8/8/8/8/8/4K3 white - - 0 1"),
+            Some(FenError::BadSideToMove("white".to_string()))
+        );
+        assert_eq!(
+            error_for("4k3/8/8/8/8/8/8/4K3 w KK - 0 1"),
+            Some(FenError::BadCastling("KK".to_string()))
+        );
+        // two rights for the same side, one as KQkq and one as a file
+        assert_eq!(
+            error_for("4k3/8/8/8/8/8/8/R3K2R w KH - 0 1"),
+            Some(FenError::BadCastling("KH".to_string()))
+        );
+        assert_eq!(
+            error_for("r3k2r/8/8/8/8/8/8/4K3 w qa - 0 1"),
+            Some(FenError::BadCastling("qa".to_string()))
+        );
+        assert_eq!(error_for("4k3/8/8/8/8/8/8/R3K2R w HA - 0 1"), None);
+        assert_eq!(
+            error_for("4k3/8/8/8/8/8/8/4K3 w - e9 0 1"),
+            Some(FenError::BadEnPassant("e9".to_string()))
+        );
+        assert_eq!(
+            error_for("4k3/8/8/8/8/8/8/4K3 w - - 0 one"),
+            Some(FenError::BadClock("one".to_string()))
+        );
+        assert_eq!(
+            error_for("4k3/8/8/8/8/8/8/4K3"),
+            Some(FenError::MissingField("side to move"))
+        );
+
+        // the fields after the side to move can be left off
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/4K3 b").unwrap();
+        assert_eq!(board.get_fen(), "4k3/8/8/8/8/8/8/4K3 b - - 0 1");
+    }
 }
@@ -0,0 +1,327 @@
+//! extended position description, the format test suites like WAC and STS come in: the first
+//! four fields of a fen followed by operations such as bm Qg6; id "WAC.001";
+use crate::board::{Board, PositionProblem, ValidationMode};
+use crate::conversion::FenError;
+use crate::moves::{Move, MoveParseError};
+use crate::san;
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EpdOperation {
+    /// bm, the moves that solve the position
+    BestMoves(Vec<Move>),
+    /// am, the moves to avoid
+    AvoidMoves(Vec<Move>),
+    /// id
+    Id(String),
+    /// c0 to c9
+    Comment(String),
+    /// acd, how deep the analysis went
+    AnalysisDepth(u32),
+    /// anything else, with its operands as they were written
+    Other(Vec<String>),
+}
+
+#[derive(Clone)]
+pub struct Epd {
+    pub board: Board,
+    /// keyed by opcode
+    pub operations: BTreeMap<String, EpdOperation>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EpdError {
+    BadFen(FenError),
+    /// a position that reads but can't be played from
+    BadPosition(Vec<PositionProblem>),
+    /// an operation that isn't closed by a semicolon or has a string left open
+    BadOperation(String),
+    /// a move in bm or am that isn't legal in the position
+    BadMove {
+        opcode: String,
+        error: MoveParseError,
+    },
+    /// acd with something other than a number
+    BadNumber {
+        opcode: String,
+        value: String,
+    },
+}
+
+impl fmt::Display for EpdError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EpdError::BadFen(error) => write!(f, "bad position: {}", error),
+            EpdError::BadPosition(problems) => write!(f, "impossible position: {:?}", problems),
+            EpdError::BadOperation(operation) => write!(f, "bad operation '{}'", operation),
+            EpdError::BadMove { opcode, error } => write!(f, "{}: {}", opcode, error),
+            EpdError::BadNumber { opcode, value } => {
+                write!(f, "{}: '{}' is not a number", opcode, value)
+            }
+        }
+    }
+}
+
+impl Epd {
+    pub fn id(&self) -> Option<&str> {
+        return match self.operations.get("id") {
+            Some(EpdOperation::Id(id)) => Some(id),
+            _ => None,
+        };
+    }
+
+    /// whether a move the engine found is one of the best moves and none of the ones to avoid
+    pub fn is_solved_by(&self, chess_move: Move) -> bool {
+        if let Some(EpdOperation::BestMoves(best_moves)) = self.operations.get("bm") {
+            if !best_moves.contains(&chess_move) {
+                return false;
+            }
+        }
+        if let Some(EpdOperation::AvoidMoves(avoid_moves)) = self.operations.get("am") {
+            if avoid_moves.contains(&chess_move) {
+                return false;
+            }
+        }
+        return true;
+    }
+}
+
+pub fn parse_epd(line: &str) -> Result<Epd, EpdError> {
+    let line = line.trim();
+    let mut fields_end = 0;
+    for _ in 0..4 {
+        let rest = &line[fields_end..];
+        let field_start = fields_end + (rest.len() - rest.trim_start().len());
+        fields_end = line[field_start..]
+            .find(char::is_whitespace)
+            .map_or(line.len(), |length| field_start + length);
+    }
+
+    let mut board = Board::from_fen(&line[..fields_end]).map_err(EpdError::BadFen)?;
+    board
+        .validate(ValidationMode::Lenient)
+        .map_err(EpdError::BadPosition)?;
+
+    let mut operations = BTreeMap::new();
+    for operands in split_operations(&line[fields_end..])? {
+        let opcode = operands[0].clone();
+        let operands = &operands[1..];
+
+        let operation = match opcode.as_str() {
+            "bm" | "am" => {
+                let mut moves = Vec::new();
+                for operand in operands {
+                    let chess_move =
+                        san::san_to_move(&board, operand).map_err(|error| EpdError::BadMove {
+                            opcode: opcode.clone(),
+                            error,
+                        })?;
+                    moves.push(chess_move);
+                }
+                if opcode == "bm" {
+                    EpdOperation::BestMoves(moves)
+                } else {
+                    EpdOperation::AvoidMoves(moves)
+                }
+            }
+            "id" => EpdOperation::Id(operands.join(" ")),
+            "c0" | "c1" | "c2" | "c3" | "c4" | "c5" | "c6" | "c7" | "c8" | "c9" => {
+                EpdOperation::Comment(operands.join(" "))
+            }
+            "acd" => {
+                let value = operands.join(" ");
+                EpdOperation::AnalysisDepth(value.parse::<u32>().map_err(|_| {
+                    EpdError::BadNumber {
+                        opcode: opcode.clone(),
+                        value,
+                    }
+                })?)
+            }
+            _ => EpdOperation::Other(operands.to_vec()),
+        };
+        operations.insert(opcode, operation);
+    }
+
+    return Ok(Epd { board, operations });
+}
+
+/// each operation's opcode and operands, with the quotes taken off string operands
+fn split_operations(text: &str) -> Result<Vec<Vec<String>>, EpdError> {
+    let mut operations = Vec::new();
+    let mut operands: Vec<String> = Vec::new();
+    let mut characters = text.chars().peekable();
+
+    while let Some(character) = characters.next() {
+        match character {
+            ';' => {
+                if operands.is_empty() {
+                    return Err(EpdError::BadOperation(";".to_string()));
+                }
+                operations.push(std::mem::take(&mut operands));
+            }
+            '"' => {
+                let mut string = String::new();
+                let mut closed = false;
+                while let Some(inner) = characters.next() {
+                    match inner {
+                        '"' => {
+                            closed = true;
+                            break;
+                        }
+                        '\\' => string.extend(characters.next()),
+                        _ => string.push(inner),
+                    }
+                }
+                if !closed {
+                    return Err(EpdError::BadOperation(format!("\"{}", string)));
+                }
+                operands.push(string);
+            }
+            _ if character.is_whitespace() => {}
+            _ => {
+                let mut token = character.to_string();
+                while let Some(&next) = characters.peek() {
+                    if next.is_whitespace() || next == ';' || next == '"' {
+                        break;
+                    }
+                    token.push(next);
+                    characters.next();
+                }
+                operands.push(token);
+            }
+        }
+    }
+
+    if !operands.is_empty() {
+        return Err(EpdError::BadOperation(operands.join(" ")));
+    }
+    return Ok(operations);
+}
+
+/// the epd line for the position and its operations, with moves in san and strings quoted
+pub fn write_epd(epd: &Epd) -> String {
+    let fen = epd.board.get_fen();
+    let mut line: Vec<&str> = fen.split(' ').take(4).collect();
+    let mut written_operations = Vec::new();
+
+    for (opcode, operation) in epd.operations.iter() {
+        let operands: Vec<String> = match operation {
+            EpdOperation::BestMoves(moves) | EpdOperation::AvoidMoves(moves) => moves
+                .iter()
+                .map(|chess_move| san::move_to_san(&epd.board, *chess_move))
+                .collect(),
+            EpdOperation::Id(string) | EpdOperation::Comment(string) => vec![quote(string)],
+            EpdOperation::AnalysisDepth(depth) => vec![depth.to_string()],
+            EpdOperation::Other(operands) => operands
+                .iter()
+                .map(|operand| {
+                    if operand.is_empty() || operand.contains([' ', ';', '"']) {
+                        quote(operand)
+                    } else {
+                        operand.clone()
+                    }
+                })
+                .collect(),
+        };
+        if operands.is_empty() {
+            written_operations.push(format!("{};", opcode));
+        } else {
+            written_operations.push(format!("{} {};", opcode, operands.join(" ")));
+        }
+    }
+
+    for operation in written_operations.iter() {
+        line.push(operation);
+    }
+    return line.join(" ");
+}
+
+fn quote(string: &str) -> String {
+    return format!("\"{}\"", string.replace('\\', "\\\\").replace('"', "\\\""));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::conversion;
+    use crate::epd::*;
+
+    #[test]
+    fn reads_typed_operations() {
+        let epd = parse_epd(
+            "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; am Qxg7+ Nxd5; \
+             id \"WAC.001\"; c0 \"mate; in 3\"; acd 12; pv Qg6 fxg6;",
+        )
+        .unwrap();
+
+        assert_eq!(epd.id(), Some("WAC.001"));
+        let best_move = epd
+            .board
+            .convert_notation_to_move("g3g6".to_string())
+            .unwrap();
+        assert_eq!(
+            epd.operations["bm"],
+            EpdOperation::BestMoves(vec![best_move])
+        );
+        assert!(epd.is_solved_by(best_move));
+        assert!(
+            matches!(&epd.operations["am"], EpdOperation::AvoidMoves(moves) if moves.len() == 2)
+        );
+        assert_eq!(
+            epd.operations["c0"],
+            EpdOperation::Comment("mate; in 3".to_string())
+        );
+        assert_eq!(epd.operations["acd"], EpdOperation::AnalysisDepth(12));
+        assert_eq!(
+            epd.operations["pv"],
+            EpdOperation::Other(vec!["Qg6".to_string(), "fxg6".to_string()])
+        );
+        assert_eq!(
+            epd.board.get_fen(),
+            "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - 0 1"
+        );
+
+        let written = write_epd(&epd);
+        assert_eq!(
+            written,
+            "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - acd 12; am Qxg7+ Nxd5; \
+             bm Qg6; c0 \"mate; in 3\"; id \"WAC.001\"; pv Qg6 fxg6;"
+        );
+        assert_eq!(parse_epd(&written).unwrap().operations, epd.operations);
+    }
+
+    #[test]
+    fn reports_bad_operations() {
+        let position = "4k3/8/8/8/8/8/8/4K2R w K -";
+        assert!(matches!(
+            parse_epd(&format!("{} bm Rh9;", position)),
+            Err(EpdError::BadMove { .. })
+        ));
+        assert!(matches!(
+            parse_epd(&format!("{} id \"open;", position)),
+            Err(EpdError::BadOperation(_))
+        ));
+        assert!(matches!(
+            parse_epd(&format!("{} acd deep;", position)),
+            Err(EpdError::BadNumber { .. })
+        ));
+        assert!(matches!(
+            parse_epd("4k3/8/8/8/8/8/8/4K2R x K - bm O-O;"),
+            Err(EpdError::BadFen(_))
+        ));
+        assert_eq!(
+            parse_epd("P3k3/8/8/8/8/8/8/4K3 w - - bm Kd2;").err(),
+            Some(EpdError::BadPosition(vec![
+                PositionProblem::PawnOnBackRank { location: (0, 0) }
+            ]))
+        );
+        let castle = parse_epd(&format!("{} bm O-O;", position)).unwrap();
+        assert_eq!(
+            conversion::convert_move_to_notation(&match &castle.operations["bm"] {
+                EpdOperation::BestMoves(moves) => moves[0],
+                _ => unreachable!(),
+            }),
+            "e1g1"
+        );
+    }
+}
@@ -1,5 +1,6 @@
+use crate::bitboard::*;
 use crate::board::Board;
-use crate::{constants::*, conversion, movegen::*};
+use crate::{attacks, constants::*, conversion};
 #[derive(Debug, Clone, Copy)]
 pub struct PieceValues {
     pub pawn: i32,
@@ -18,6 +19,17 @@ pub const PIECE_VALUES: PieceValues = PieceValues {
     queen: 900,
     king: 20000,
 };
+pub fn get_piece_value(piece: i8) -> i32 {
+    return match piece {
+        PAWN => PIECE_VALUES.pawn,
+        KNIGHT => PIECE_VALUES.knight,
+        BISHOP => PIECE_VALUES.bishop,
+        ROOK => PIECE_VALUES.rook,
+        QUEEN => PIECE_VALUES.queen,
+        KING => PIECE_VALUES.king,
+        _ => 0,
+    };
+}
 pub fn is_endgame(board: &Board) -> bool {
     // add presence of queens tot he board and ply data.
     if board.ply > 50 {
@@ -28,35 +40,26 @@ pub fn is_endgame(board: &Board) -> bool {
 }
 pub fn evaluate(board: &Board) -> i32 {
     let mut score: i32 = 0;
+    let endgame = is_endgame(board);
 
-    for (row_index, row) in board.colour_array.iter().enumerate() {
-        for (column_index, colour) in row.iter().enumerate() {
-            let square = board.get_piece((row_index, column_index));
-
-            if square == EMPTY {
-                continue;
-            }
+    for colour in [WHITE, BLACK] {
+        for piece in PAWN..=KING {
+            for square in Squares(board.piece_bitboard(piece, colour)) {
+                let location = location_from_square(square);
 
-            let mut score_for_piece_type = match square {
-                PAWN => PIECE_VALUES.pawn,
-                KNIGHT => PIECE_VALUES.knight,
-                BISHOP => PIECE_VALUES.bishop,
-                ROOK => PIECE_VALUES.rook,
-                QUEEN => PIECE_VALUES.queen,
-                KING => PIECE_VALUES.king,
-                _ => 0,
-            };
+                let mut score_for_piece_type = get_piece_value(piece);
 
-            score_for_piece_type += if is_endgame(board) {
-                conversion::get_piece_square_value_eg((row_index, column_index), square, *colour)
-            } else {
-                conversion::get_piece_square_value_mg((row_index, column_index), square, *colour)
-            };
+                score_for_piece_type += if endgame {
+                    conversion::get_piece_square_value_eg(location, piece, colour)
+                } else {
+                    conversion::get_piece_square_value_mg(location, piece, colour)
+                };
 
-            if colour != &board.side_to_move {
-                score_for_piece_type *= -1;
+                if colour != board.side_to_move {
+                    score_for_piece_type *= -1;
+                }
+                score += score_for_piece_type;
             }
-            score += score_for_piece_type as i32;
         }
     }
     return score;
@@ -69,41 +72,18 @@ pub fn is_in_check(
 ) -> bool {
     let opponent_colour = if side_to_check == WHITE { BLACK } else { WHITE };
 
-    let king_location = board.get_king_location(side_to_check);
-
-    for (row_index, row) in board.colour_array.iter().enumerate() {
-        for (column_index, square_colour) in row.iter().enumerate() {
-            if square_colour != &opponent_colour {
-                continue;
-            }
-            let piece_type = board.get_piece((row_index, column_index));
-
-            let outcome = is_attacked_by_piece_from_square(
-                board,
-                (row_index, column_index),
-                piece_type,
-                king_location.unwrap(),
-                opponent_colour,
-            );
-
-            if outcome {
-                return outcome;
-            }
+    // a board that was never validated can be missing a king, which can't be in check
+    let Some(king_location) = board.get_king_location(side_to_check) else {
+        return false;
+    };
 
-            if aditional_square_to_check.is_some() {
-                let outcome = is_attacked_by_piece_from_square(
-                    board,
-                    (row_index, column_index),
-                    piece_type,
-                    aditional_square_to_check.unwrap(),
-                    opponent_colour,
-                );
+    if board.attackers_to(square_from_location(king_location), opponent_colour) != EMPTY_BITBOARD {
+        return true;
+    }
 
-                if outcome {
-                    return outcome;
-                }
-            }
-        }
+    if let Some(additional_square) = aditional_square_to_check {
+        return board.attackers_to(square_from_location(additional_square), opponent_colour)
+            != EMPTY_BITBOARD;
     }
     return false;
 }
@@ -112,168 +92,22 @@ pub fn get_safety_score(board: &Board, square: (usize, usize), side_to_check: i8
     let mut number_of_attackers = 0;
 
     let piece_attack_weight = [1, 20, 20, 40, 80, 1];
-    let square_direction = [
-        (1, 0),
-        (1, -1),
-        (0, -1),
-        (-1, -1),
-        (-1, 0),
-        (-1, 1),
-        (0, 1),
-        (1, 1),
-        (0, 0),
-    ];
-    // for the square,  get it and all the surrounding squares locations.
-    // for each of those squares, check if it is attacked.
+    // for the square, get it and all the surrounding squares.
+    // for each of those squares, weigh its least valuable attacker.
     let opponent_colour = if side_to_check == WHITE { BLACK } else { WHITE };
+    let square = square_from_location(square);
 
-    for direction in square_direction.iter() {
-        let square_to_check = (square.0 as i8 + direction.0, square.1 as i8 + direction.1);
-        if square_to_check.0 < 0
-            || square_to_check.0 > 7
-            || square_to_check.1 < 0
-            || square_to_check.1 > 7
+    for square_to_check in Squares(attacks::king_attacks(square) | (1u64 << square)) {
+        let attackers = board.attackers_to(square_to_check, opponent_colour);
+        if let Some(piece_type) = (PAWN..=KING)
+            .find(|&piece_type| attackers & board.piece_bitboards[piece_index(piece_type)] != 0)
         {
-            continue;
-        }
-        let mut square_checked = false;
-        for (row_index, row) in board.colour_array.iter().enumerate() {
-            for (column_index, square_colour) in row.iter().enumerate() {
-                if square_colour != &opponent_colour {
-                    continue;
-                }
-
-                let piece_type = board.get_piece((row_index, column_index));
-
-                let outcome = is_attacked_by_piece_from_square(
-                    board,
-                    (row_index, column_index),
-                    piece_type,
-                    (square_to_check.0 as usize, square_to_check.1 as usize),
-                    opponent_colour,
-                );
-
-                if outcome {
-                    number_of_attackers += 1;
-                    safety_score += piece_attack_weight[piece_type as usize - 1];
-                    square_checked = true;
-                }
-                if square_checked {
-                    break;
-                }
-            }
-
-            if square_checked {
-                break;
-            }
+            number_of_attackers += 1;
+            safety_score += piece_attack_weight[piece_type as usize - 1];
         }
     }
     return number_of_attackers * safety_score;
 }
-// in case of castling, check if they attack the intermediary squares.
-
-// its given a square, and an enemy piece.
-// and if the square is attacked by the enemy piece it returns true.
-pub fn is_attacked_by_piece_from_square(
-    board: &Board,
-    square_from: (usize, usize),
-    piece_type: i8,
-    square_to: (usize, usize),
-    side_to_generate_for: i8,
-) -> bool {
-    let difference_in_row = (square_to.0 as i32 - square_from.0 as i32).abs();
-    let difference_in_column = (square_to.1 as i32 - square_from.1 as i32).abs();
-
-    match piece_type {
-        PAWN => {
-            if difference_in_column > 1
-                || difference_in_row > 1
-                || (difference_in_column == 0 && difference_in_row == 1)
-                || (difference_in_column == 1 && difference_in_row == 0)
-            {
-                return false;
-            };
-            for attack in get_pawn_attacks(square_from, side_to_generate_for, board) {
-                if attack == square_to {
-                    return true;
-                }
-            }
-        }
-        KNIGHT => {
-            if difference_in_row > 2
-                || difference_in_column > 2
-                || (difference_in_row < 2 && difference_in_column < 2)
-            {
-                return false;
-            };
-            for attack in get_knight_attacks(square_from, side_to_generate_for, board) {
-                if attack == square_to {
-                    return true;
-                }
-            }
-        }
-        BISHOP => {
-            if difference_in_column == 0 || difference_in_row == 0 {
-                return false;
-            }
-
-            if difference_in_row != difference_in_column {
-                return false;
-            }
-
-            for attack in get_bishop_attacks(square_from, side_to_generate_for, board) {
-                if attack == square_to {
-                    return true;
-                }
-            }
-        }
-        ROOK => {
-            if difference_in_row != 0 && difference_in_column != 0 {
-                return false;
-            }
-            for attack in get_rook_attacks(square_from, side_to_generate_for, board) {
-                if attack == square_to {
-                    return true;
-                }
-            }
-        }
-        QUEEN => {
-            if (difference_in_row != 0 && difference_in_column != 0)
-                && (difference_in_row != difference_in_column)
-            {
-                return false;
-            }
-            for attack in get_queen_moves(square_from, side_to_generate_for, board) {
-                if attack == square_to {
-                    return true;
-                }
-            }
-        }
-        KING => {
-            if difference_in_row > 1 || difference_in_column > 1 {
-                return false;
-            }
-            for attack in get_king_attacks(square_from, side_to_generate_for, board) {
-                if attack == square_to {
-                    return true;
-                }
-            }
-        }
-        _ => return false,
-    }
-    // for a given piece, on square from, does it attack the square_to?
-    // can easily ignore pawns, kings, and knights outside a certain range
-
-    // ignore rooks on wrong rank / file
-
-    // check if bishop or queen on same diagonal.
-
-    // is attack blocked by another piece (of any colour)
-
-    // if queen not attacking along diagonal, check on correct file for rook-like attack.
-
-    return false;
-}
 
 #[cfg(test)]
 mod tests {
@@ -311,7 +145,11 @@ mod tests {
         let board = conversion::convert_fen_to_board("Q1k5/8/1K6/8/8/5B2/8/8 b - - 0 64");
 
         let eval = evaluate::evaluate(&board);
-        assert!(eval < -100, "position favours white!");
+        // the score is from the side to move's point of view, so black to move scores it low
+        assert!(
+            eval < -100,
+            "white is winning, black to move should score it below -100"
+        );
     }
 
     #[test]
@@ -320,14 +158,38 @@ mod tests {
             conversion::convert_fen_to_board("5k2/5p2/4pQp1/4P1Np/7P/6P1/4qP1K/8 b - - 10 41");
 
         let eval = evaluate::evaluate(&board);
-        assert!(eval > 100, "position does not favour white!");
+        assert!(
+            eval < -100,
+            "white is winning, black to move should score it below -100"
+        );
     }
     #[test]
     fn evaluate_black_1() {
         let board = conversion::convert_fen_to_board("1k6/7p/4q3/3n4/3K4/2q5/7P/8 w - - 2 50");
 
         let eval = evaluate::evaluate(&board);
-        assert!(eval > 100, "position does not favour black!");
+        assert!(
+            eval < -100,
+            "black is winning, white to move should score it below -100"
+        );
+    }
+
+    #[test]
+    fn a_side_without_a_king_is_not_in_check() {
+        let board = conversion::convert_fen_to_board("8/8/8/8/8/8/8/R3K3 b - - 0 1");
+
+        assert!(!evaluate::is_in_check(
+            &board,
+            crate::constants::BLACK,
+            Some((0, 0))
+        ));
+        assert!(!evaluate::is_in_check(
+            &board,
+            crate::constants::WHITE,
+            None
+        ));
+        // so the outcome can be asked for without validating first. black has no moves at all
+        assert_eq!(board.outcome(), Some(crate::board::GameOutcome::Stalemate));
     }
 
     // test black favoured position favour black
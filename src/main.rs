@@ -1,12 +1,49 @@
+// the codebase prefers explicit returns
+#![allow(clippy::needless_return)]
+
+pub mod attacks;
 pub mod bench;
+pub mod bitboard;
 pub mod board;
 pub mod constants;
 pub mod conversion;
+pub mod epd;
 pub mod evaluate;
 pub mod movegen;
+pub mod movepick;
 pub mod moves;
+pub mod perft;
+pub mod pgn;
+pub mod san;
 pub mod search;
+pub mod see;
 pub mod uci;
+pub mod zobrist;
 fn main() {
-    uci::run();
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    if arguments.is_empty() {
+        uci::run();
+        return;
+    }
+
+    if arguments[0] == "perftsuite" {
+        attacks::init();
+        match perft::perft_suite_command(arguments[1..].iter().map(String::as_str)) {
+            Ok(summary) if summary.failed == 0 => return,
+            Ok(_) => std::process::exit(1),
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // anything else on the command line is a perftree query
+    match uci::perftree(&arguments) {
+        Ok(output) => println!("{}", output),
+        Err(error) => {
+            eprintln!("{}", error);
+            std::process::exit(1);
+        }
+    }
 }
@@ -0,0 +1,926 @@
+use crate::{attacks, bitboard::*, board::*, constants::*, moves::*};
+
+pub fn get_pawn_attacks(square: (usize, usize), side_to_generate_for: i8, board: &Board) -> u64 {
+    // does not include en passant
+    let enemy_colour = if side_to_generate_for == WHITE {
+        BLACK
+    } else {
+        WHITE
+    };
+
+    return attacks::pawn_attacks(square_from_location(square), side_to_generate_for)
+        & board.colour_bitboard(enemy_colour);
+}
+/// which moves a generator should produce. captures also covers en passant and promoting to a
+/// queen, quiets covers everything else, so the two together are every move
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveType {
+    All,
+    Captures,
+    Quiets,
+}
+
+fn includes_promotion_to(move_type: MoveType, piece: i8) -> bool {
+    return match move_type {
+        MoveType::All => true,
+        MoveType::Captures => piece == QUEEN,
+        MoveType::Quiets => piece != QUEEN,
+    };
+}
+
+/// the squares a non pawn move of this type can land on
+fn target_squares(move_type: MoveType, side_to_generate_for: i8, board: &Board) -> u64 {
+    let enemy_colour = if side_to_generate_for == WHITE {
+        BLACK
+    } else {
+        WHITE
+    };
+    return match move_type {
+        MoveType::All => !board.colour_bitboard(side_to_generate_for),
+        MoveType::Captures => board.colour_bitboard(enemy_colour),
+        MoveType::Quiets => !board.occupied(),
+    };
+}
+
+pub fn generate_pawn_moves(
+    square: (usize, usize),
+    side_to_generate_for: i8,
+    board: &Board,
+    move_type: MoveType,
+    moves: &mut MoveList,
+) {
+    let mut blocked = false;
+    let direction_of_pawns: i8 = match side_to_generate_for {
+        1 => -1,
+        -1 => 1,
+        _ => 0,
+    };
+
+    // know if double jump allowed if from starting row
+    let starting_row = if side_to_generate_for == 1 { 6 } else { 1 };
+
+    // second rank for promotion
+    let promotion_row = if side_to_generate_for == 1 { 1 } else { 6 };
+
+    let (row, column) = square;
+
+    // if square in front of pawn is not filled, can move there. a pawn already on its last rank,
+    // which only an unvalidated position can have, has nowhere to go
+    let Some(index_of_square_in_front) = row
+        .checked_add_signed(direction_of_pawns as isize)
+        .filter(|&row_in_front| row_in_front < 8)
+    else {
+        return;
+    };
+
+    let occupied = board.occupied();
+    if occupied & square_bit((index_of_square_in_front, column)) != EMPTY_BITBOARD {
+        blocked = true;
+    };
+
+    if row == promotion_row && !blocked {
+        for piece in [KNIGHT, BISHOP, ROOK, QUEEN] {
+            if includes_promotion_to(move_type, piece) {
+                moves.push(Move::new(
+                    square,
+                    (index_of_square_in_front, column),
+                    promotion_flag(piece, false),
+                ));
+            }
+        }
+    } else if !blocked && move_type != MoveType::Captures {
+        moves.push(Move::new(
+            square,
+            (index_of_square_in_front, column),
+            QUIET_MOVE,
+        ));
+    }
+
+    // if there is a square diagonally forward from the pawn possessed by enemy
+    let attack_squares = get_pawn_attacks(square, side_to_generate_for, board);
+
+    for attack_square in Squares(attack_squares).map(location_from_square) {
+        // if in the promotion row, you must also promote
+        if row == promotion_row {
+            for piece in [KNIGHT, BISHOP, ROOK, QUEEN] {
+                if includes_promotion_to(move_type, piece) {
+                    moves.push(Move::new(
+                        square,
+                        attack_square,
+                        promotion_flag(piece, true),
+                    ));
+                }
+            }
+        } else if move_type != MoveType::Quiets {
+            moves.push(Move::new(square, attack_square, CAPTURE));
+        }
+    }
+
+    if row == starting_row && !blocked && move_type != MoveType::Captures {
+        // if pawn on its starting square, can move two
+        let index_of_square_in_front = if direction_of_pawns.is_negative() {
+            row - 2
+        } else {
+            row + 2
+        };
+
+        if occupied & square_bit((index_of_square_in_front, column)) == EMPTY_BITBOARD {
+            moves.push(Move::new(
+                square,
+                (index_of_square_in_front, column),
+                DOUBLE_PAWN_PUSH,
+            ));
+        }
+    }
+
+    if move_type == MoveType::Quiets {
+        return;
+    }
+
+    // if previous move was a double push, and this pawn is on same row but off by one column, add en passant
+    if let Some(move_info) = board.en_passant_location {
+        if move_info.0 == row && move_info.1.abs_diff(column) == 1 {
+            moves.push(Move::new(
+                square,
+                (index_of_square_in_front, move_info.1),
+                EN_PASSANT_CAPTURE,
+            ));
+        }
+    }
+}
+
+/// push a move to every square in the bitboard, as a capture where an enemy piece stands
+fn push_moves_to_squares(
+    square: (usize, usize),
+    target_squares: u64,
+    board: &Board,
+    moves: &mut MoveList,
+) {
+    let occupied = board.occupied();
+    for target_square in Squares(target_squares) {
+        let flag = if occupied & (1u64 << target_square) != EMPTY_BITBOARD {
+            CAPTURE
+        } else {
+            QUIET_MOVE
+        };
+        moves.push(Move::new(square, location_from_square(target_square), flag));
+    }
+}
+pub fn get_knight_attacks(square: (usize, usize), side_to_generate_for: i8, board: &Board) -> u64 {
+    // if populated by same colour piece, no move
+    return attacks::knight_attacks(square_from_location(square))
+        & !board.colour_bitboard(side_to_generate_for);
+}
+pub fn generate_knight_moves(
+    square: (usize, usize),
+    side_to_generate_for: i8,
+    board: &Board,
+    move_type: MoveType,
+    moves: &mut MoveList,
+) {
+    let attack_squares = get_knight_attacks(square, side_to_generate_for, board)
+        & target_squares(move_type, side_to_generate_for, board);
+    push_moves_to_squares(square, attack_squares, board, moves);
+}
+pub fn get_bishop_attacks(square: (usize, usize), side_to_generate_for: i8, board: &Board) -> u64 {
+    return attacks::bishop_attacks(square_from_location(square), board.occupied())
+        & !board.colour_bitboard(side_to_generate_for);
+}
+pub fn generate_bishop_moves(
+    square: (usize, usize),
+    side_to_generate_for: i8,
+    board: &Board,
+    move_type: MoveType,
+    moves: &mut MoveList,
+) {
+    let attack_squares = get_bishop_attacks(square, side_to_generate_for, board)
+        & target_squares(move_type, side_to_generate_for, board);
+    push_moves_to_squares(square, attack_squares, board, moves);
+}
+pub fn get_rook_attacks(square: (usize, usize), side_to_generate_for: i8, board: &Board) -> u64 {
+    return attacks::rook_attacks(square_from_location(square), board.occupied())
+        & !board.colour_bitboard(side_to_generate_for);
+}
+pub fn generate_rook_moves(
+    square: (usize, usize),
+    side_to_generate_for: i8,
+    board: &Board,
+    move_type: MoveType,
+    moves: &mut MoveList,
+) {
+    let attack_squares = get_rook_attacks(square, side_to_generate_for, board)
+        & target_squares(move_type, side_to_generate_for, board);
+    push_moves_to_squares(square, attack_squares, board, moves);
+}
+pub fn get_queen_moves(square: (usize, usize), side_to_generate_for: i8, board: &Board) -> u64 {
+    return attacks::queen_attacks(square_from_location(square), board.occupied())
+        & !board.colour_bitboard(side_to_generate_for);
+}
+pub fn generate_queen_moves(
+    square: (usize, usize),
+    side_to_generate_for: i8,
+    board: &Board,
+    move_type: MoveType,
+    moves: &mut MoveList,
+) {
+    let attack_squares = get_queen_moves(square, side_to_generate_for, board)
+        & target_squares(move_type, side_to_generate_for, board);
+    push_moves_to_squares(square, attack_squares, board, moves);
+}
+pub fn get_king_attacks(square: (usize, usize), side_to_generate_for: i8, board: &Board) -> u64 {
+    return attacks::king_attacks(square_from_location(square))
+        & !board.colour_bitboard(side_to_generate_for);
+}
+/// generate pseudo legal king moves,
+/// this includes castling
+/// this does not check the king is not being moved into check
+pub fn generate_king_moves(
+    square: (usize, usize),
+    side_to_generate_for: i8,
+    board: &Board,
+    is_in_check: bool,
+    move_type: MoveType,
+    moves: &mut MoveList,
+) {
+    // when castling, take into account that the king is moving through the squares, not teleporting
+    // only for those squares castling still possible
+    let (row, _) = square;
+    let attack_squares = get_king_attacks(square, side_to_generate_for, board)
+        & target_squares(move_type, side_to_generate_for, board);
+    push_moves_to_squares(square, attack_squares, board, moves);
+
+    // if in check, don't generate castling
+    if is_in_check || move_type == MoveType::Captures || row != back_row(side_to_generate_for) {
+        return;
+    }
+
+    for castle_side in [QUEENSIDE, KINGSIDE] {
+        let Some(rook_file) = board.castling_rook_file(side_to_generate_for, castle_side) else {
+            continue;
+        };
+        let rook_location = (row, rook_file);
+        if board.piece_bitboard(ROOK, side_to_generate_for) & square_bit(rook_location)
+            == EMPTY_BITBOARD
+        {
+            continue;
+        }
+
+        if castling_path(square, rook_location, castle_side)
+            & castling_blockers(board, square, rook_location)
+            == EMPTY_BITBOARD
+        {
+            let flag = if castle_side == KINGSIDE {
+                KING_CASTLE
+            } else {
+                QUEEN_CASTLE
+            };
+            moves.push(Move::new(square, rook_location, flag));
+        }
+    }
+}
+
+/// every square the king or rook passes over or lands on when castling. in chess960 the king
+/// and rook can start anywhere on the back row, so this is worked out rather than fixed
+fn castling_path(
+    king_location: (usize, usize),
+    rook_location: (usize, usize),
+    castle_side: usize,
+) -> u64 {
+    let (king_to, rook_to) = castling_targets(king_location.0, castle_side);
+    let king_square = square_from_location(king_location);
+    let rook_square = square_from_location(rook_location);
+
+    return attacks::squares_between(king_square, square_from_location(king_to))
+        | square_bit(king_to)
+        | attacks::squares_between(rook_square, square_from_location(rook_to))
+        | square_bit(rook_to);
+}
+
+/// everything on the board apart from the castling king and rook themselves
+fn castling_blockers(
+    board: &Board,
+    king_location: (usize, usize),
+    rook_location: (usize, usize),
+) -> u64 {
+    return board.occupied() & !square_bit(king_location) & !square_bit(rook_location);
+}
+
+pub fn generate_pseudo_legal_moves(
+    board: &Board,
+    side_to_generate_for: i8,
+    is_in_check: bool,
+) -> MoveList {
+    let mut moves = MoveList::new();
+
+    // go through each piece of the side to move, one piece type at a time
+    for piece in PAWN..=KING {
+        for square in Squares(board.piece_bitboard(piece, side_to_generate_for)) {
+            let location = location_from_square(square);
+            generate_piece_moves(
+                piece,
+                location,
+                side_to_generate_for,
+                board,
+                is_in_check,
+                MoveType::All,
+                &mut moves,
+            );
+        }
+    }
+
+    return moves;
+}
+
+fn generate_piece_moves(
+    piece: i8,
+    location: (usize, usize),
+    side_to_generate_for: i8,
+    board: &Board,
+    is_in_check: bool,
+    move_type: MoveType,
+    moves: &mut MoveList,
+) {
+    match piece {
+        PAWN => generate_pawn_moves(location, side_to_generate_for, board, move_type, moves),
+        KNIGHT => generate_knight_moves(location, side_to_generate_for, board, move_type, moves),
+        BISHOP => generate_bishop_moves(location, side_to_generate_for, board, move_type, moves),
+        ROOK => generate_rook_moves(location, side_to_generate_for, board, move_type, moves),
+        QUEEN => generate_queen_moves(location, side_to_generate_for, board, move_type, moves),
+        KING => generate_king_moves(
+            location,
+            side_to_generate_for,
+            board,
+            is_in_check,
+            move_type,
+            moves,
+        ),
+        _ => {}
+    }
+}
+
+/// every piece of the given colour that attacks the square, with sliders blocked by `occupied`
+pub fn attackers_to(board: &Board, square: usize, colour: i8, occupied: u64) -> u64 {
+    let enemy_colour = if colour == WHITE { BLACK } else { WHITE };
+    let bishops_and_queens =
+        board.piece_bitboard(BISHOP, colour) | board.piece_bitboard(QUEEN, colour);
+    let rooks_and_queens = board.piece_bitboard(ROOK, colour) | board.piece_bitboard(QUEEN, colour);
+
+    // a pawn of the other colour on the square attacks exactly the squares our pawns attack it from
+    return (attacks::pawn_attacks(square, enemy_colour) & board.piece_bitboard(PAWN, colour))
+        | (attacks::knight_attacks(square) & board.piece_bitboard(KNIGHT, colour))
+        | (attacks::king_attacks(square) & board.piece_bitboard(KING, colour))
+        | (attacks::bishop_attacks(square, occupied) & bishops_and_queens)
+        | (attacks::rook_attacks(square, occupied) & rooks_and_queens);
+}
+
+/// pieces of the given colour that are the only thing between their king and an enemy slider
+pub fn get_pinned_pieces(board: &Board, colour: i8, king_square: usize) -> u64 {
+    let enemy_colour = if colour == WHITE { BLACK } else { WHITE };
+    let enemy_pieces = board.colour_bitboard(enemy_colour);
+    let occupied = board.occupied();
+    let mut pinned = EMPTY_BITBOARD;
+
+    // enemy sliders that would hit the king if none of our pieces were in the way
+    let snipers = (attacks::bishop_attacks(king_square, enemy_pieces)
+        & (board.piece_bitboard(BISHOP, enemy_colour) | board.piece_bitboard(QUEEN, enemy_colour)))
+        | (attacks::rook_attacks(king_square, enemy_pieces)
+            & (board.piece_bitboard(ROOK, enemy_colour)
+                | board.piece_bitboard(QUEEN, enemy_colour)));
+
+    for sniper in Squares(snipers) {
+        let blockers = attacks::squares_between(king_square, sniper) & occupied;
+        if blockers.count_ones() == 1 && blockers & board.colour_bitboard(colour) != EMPTY_BITBOARD
+        {
+            pinned |= blockers;
+        }
+    }
+
+    return pinned;
+}
+
+/// generate only the legal moves for the side to move.
+/// works out the checking pieces, the pinned pieces and which squares block or capture a check up
+/// front, so no move has to be made to see if it leaves the king in check
+pub fn generate_legal_moves(board: &Board) -> MoveList {
+    let mut moves = MoveList::new();
+    generate_legal_moves_of_type(board, MoveType::All, &mut moves);
+    return moves;
+}
+
+/// the legal captures, en passant included, and promotions to a queen. what the quiescence
+/// search looks at when not in check
+pub fn generate_captures(board: &Board) -> MoveList {
+    let mut moves = MoveList::new();
+    generate_legal_moves_of_type(board, MoveType::Captures, &mut moves);
+    return moves;
+}
+
+/// the legal moves out of check: the king stepping to a safe square, or with a single checker,
+/// capturing it or blocking its line. only pieces that can land on those squares are looked at.
+/// when not in check this is the same as `generate_legal_moves`
+pub fn generate_evasions(board: &Board) -> MoveList {
+    let side = board.side_to_move;
+    let enemy_colour = if side == WHITE { BLACK } else { WHITE };
+
+    let Some(king_location) = board.get_king_location(side) else {
+        return generate_legal_moves(board);
+    };
+    let king_square = square_from_location(king_location);
+    let checkers = attackers_to(board, king_square, enemy_colour, board.occupied());
+    if checkers == EMPTY_BITBOARD {
+        return generate_legal_moves(board);
+    }
+
+    let mut moves = MoveList::new();
+
+    // castling is never allowed out of check
+    let king_targets = get_king_attacks(king_location, side, board);
+    push_moves_to_squares(king_location, king_targets, board, &mut moves);
+    moves.retain(|king_move| is_king_move_legal(board, king_move, king_square, enemy_colour));
+
+    // only the king can get out of double check
+    if checkers.count_ones() > 1 {
+        return moves;
+    }
+
+    let checker_square = checkers.trailing_zeros() as usize;
+    let check_mask = checkers | attacks::squares_between(king_square, checker_square);
+
+    // a pinned piece can only move along its pin, which never crosses the check
+    let movable = board.colour_bitboard(side)
+        & !board.piece_bitboard(KING, side)
+        & !get_pinned_pieces(board, side, king_square);
+
+    for square in Squares(movable & board.piece_bitboards[piece_index(PAWN)]) {
+        let first_move = moves.len();
+        generate_pawn_moves(
+            location_from_square(square),
+            side,
+            board,
+            MoveType::All,
+            &mut moves,
+        );
+
+        moves.retain_from(first_move, |pawn_move| {
+            if pawn_move.is_en_passant() {
+                // only helps if the pawn being taken is the checker, or the capture blocks
+                let captured_square = square_from_location((pawn_move.from().0, pawn_move.to().1));
+                return (captured_square == checker_square
+                    || (1u64 << pawn_move.to_square()) & check_mask != EMPTY_BITBOARD)
+                    && is_en_passant_legal(board, pawn_move, king_square, enemy_colour);
+            }
+            return (1u64 << pawn_move.to_square()) & check_mask != EMPTY_BITBOARD;
+        });
+    }
+
+    for piece in KNIGHT..=QUEEN {
+        for square in Squares(movable & board.piece_bitboards[piece_index(piece)]) {
+            let location = location_from_square(square);
+            let targets = match piece {
+                KNIGHT => get_knight_attacks(location, side, board),
+                BISHOP => get_bishop_attacks(location, side, board),
+                ROOK => get_rook_attacks(location, side, board),
+                _ => get_queen_moves(location, side, board),
+            };
+            push_moves_to_squares(location, targets & check_mask, board, &mut moves);
+        }
+    }
+
+    return moves;
+}
+
+/// add the legal moves of one type to the list, after whatever is already in it
+pub fn generate_legal_moves_of_type(board: &Board, move_type: MoveType, moves: &mut MoveList) {
+    let side = board.side_to_move;
+    let enemy_colour = if side == WHITE { BLACK } else { WHITE };
+
+    // without a king nothing can be illegal
+    let Some(king_location) = board.get_king_location(side) else {
+        for piece in PAWN..=KING {
+            for square in Squares(board.piece_bitboard(piece, side)) {
+                let location = location_from_square(square);
+                generate_piece_moves(piece, location, side, board, false, move_type, moves);
+            }
+        }
+        return;
+    };
+    let king_square = square_from_location(king_location);
+    let occupied = board.occupied();
+
+    let checkers = attackers_to(board, king_square, enemy_colour, occupied);
+    let pinned = get_pinned_pieces(board, side, king_square);
+
+    let check_mask = check_mask(king_square, checkers);
+
+    for piece in PAWN..=KING {
+        if piece != KING && check_mask == EMPTY_BITBOARD {
+            continue;
+        }
+
+        for square in Squares(board.piece_bitboard(piece, side)) {
+            // a pinned knight can never move
+            if piece == KNIGHT && pinned & (1u64 << square) != EMPTY_BITBOARD {
+                continue;
+            }
+
+            let first_move = moves.len();
+            let location = location_from_square(square);
+            generate_piece_moves(
+                piece,
+                location,
+                side,
+                board,
+                checkers != EMPTY_BITBOARD,
+                move_type,
+                moves,
+            );
+
+            moves.retain_from(first_move, |generated_move| {
+                let to_square = generated_move.to_square();
+
+                if piece == KING {
+                    return is_king_move_legal(board, generated_move, king_square, enemy_colour);
+                }
+                if generated_move.is_en_passant() {
+                    return is_en_passant_legal(board, generated_move, king_square, enemy_colour);
+                }
+                return (1u64 << to_square) & check_mask != EMPTY_BITBOARD
+                    && (pinned & (1u64 << square) == EMPTY_BITBOARD
+                        || attacks::line_through(king_square, square) & (1u64 << to_square)
+                            != EMPTY_BITBOARD);
+            });
+        }
+    }
+}
+
+/// the squares a non king move must land on: anywhere, onto or in front of a single checker,
+/// or nowhere when in double check
+fn check_mask(king_square: usize, checkers: u64) -> u64 {
+    return match checkers.count_ones() {
+        0 => !EMPTY_BITBOARD,
+        1 => checkers | attacks::squares_between(king_square, checkers.trailing_zeros() as usize),
+        _ => EMPTY_BITBOARD,
+    };
+}
+
+/// whether generate_pseudo_legal_moves would produce the move for the side to move, worked out
+/// from the move alone. for checking moves from the hash table or killer slots
+pub fn is_pseudo_legal(board: &Board, chess_move: Move) -> bool {
+    let side = board.side_to_move;
+    let from = chess_move.from();
+    let to = chess_move.to();
+    let from_bit = square_bit(from);
+    let to_bit = square_bit(to);
+
+    // flags 6 and 7 aren't used by any move
+    if board.colour_bitboard(side) & from_bit == EMPTY_BITBOARD
+        || matches!(chess_move.flag(), 6 | 7)
+    {
+        return false;
+    }
+    let piece = board.get_piece(from);
+    let occupied = board.occupied();
+    let enemy_pieces = board.colour_bitboard(-side);
+
+    if chess_move.is_castle() {
+        let castle_side = if chess_move.flag() == KING_CASTLE {
+            KINGSIDE
+        } else {
+            QUEENSIDE
+        };
+        let row = back_row(side);
+        let is_in_check =
+            attackers_to(board, chess_move.from_square(), -side, occupied) != EMPTY_BITBOARD;
+
+        return piece == KING
+            && !is_in_check
+            && from.0 == row
+            && to.0 == row
+            && board.castling_rook_file(side, castle_side) == Some(to.1)
+            && board.piece_bitboard(ROOK, side) & to_bit != EMPTY_BITBOARD
+            && castling_path(from, to, castle_side) & castling_blockers(board, from, to)
+                == EMPTY_BITBOARD;
+    }
+
+    // the flag a capture or quiet move to the square would be generated with
+    let lands_on_enemy = enemy_pieces & to_bit != EMPTY_BITBOARD;
+
+    if piece != PAWN {
+        let plain_flag = if lands_on_enemy { CAPTURE } else { QUIET_MOVE };
+        return chess_move.flag() == plain_flag
+            && attacks::piece_attacks(piece, chess_move.from_square(), side, occupied)
+                & !board.colour_bitboard(side)
+                & to_bit
+                != EMPTY_BITBOARD;
+    }
+
+    let direction: isize = if side == WHITE { -1 } else { 1 };
+    let starting_row = if side == WHITE { 6 } else { 1 };
+    let promotion_row = if side == WHITE { 1 } else { 6 };
+    let forward_row = from.0 as isize + direction;
+
+    // pawns on the row before the last have to promote, and nothing else can
+    if chess_move.is_promotion() != (from.0 == promotion_row) {
+        return false;
+    }
+
+    if to.1 == from.1 {
+        if occupied & to_bit != EMPTY_BITBOARD || chess_move.is_capture() {
+            return false;
+        }
+        if to.0 as isize == forward_row {
+            return chess_move.is_promotion() || chess_move.flag() == QUIET_MOVE;
+        }
+        // the double push also needs the square it passes over to be empty
+        return chess_move.flag() == DOUBLE_PAWN_PUSH
+            && from.0 == starting_row
+            && to.0 as isize == forward_row + direction
+            && occupied & square_bit((forward_row as usize, from.1)) == EMPTY_BITBOARD;
+    }
+
+    if to.0 as isize != forward_row || to.1.abs_diff(from.1) != 1 {
+        return false;
+    }
+    if chess_move.is_en_passant() {
+        return board.en_passant_location == Some((from.0, to.1));
+    }
+    return chess_move.is_capture() && lands_on_enemy;
+}
+
+/// whether the move is pseudo legal and doesn't leave the side to move's king in check
+pub fn is_legal(board: &Board, chess_move: Move) -> bool {
+    if !is_pseudo_legal(board, chess_move) {
+        return false;
+    }
+
+    let side = board.side_to_move;
+    let enemy_colour = -side;
+    let Some(king_location) = board.get_king_location(side) else {
+        return true;
+    };
+    let king_square = square_from_location(king_location);
+
+    if chess_move.from_square() == king_square {
+        return is_king_move_legal(board, chess_move, king_square, enemy_colour);
+    }
+    if chess_move.is_en_passant() {
+        return is_en_passant_legal(board, chess_move, king_square, enemy_colour);
+    }
+
+    let checkers = attackers_to(board, king_square, enemy_colour, board.occupied());
+    let from_bit = 1u64 << chess_move.from_square();
+    let to_bit = 1u64 << chess_move.to_square();
+
+    return check_mask(king_square, checkers) & to_bit != EMPTY_BITBOARD
+        && (get_pinned_pieces(board, side, king_square) & from_bit == EMPTY_BITBOARD
+            || attacks::line_through(king_square, chess_move.from_square()) & to_bit
+                != EMPTY_BITBOARD);
+}
+
+/// whether the move checks the other king, directly or by uncovering a slider, without making
+/// it. the move has to be pseudo legal
+pub fn gives_check(board: &Board, chess_move: Move) -> bool {
+    let side = board.side_to_move;
+    let Some(enemy_king) = board.get_king_location(-side) else {
+        return false;
+    };
+    let enemy_king_square = square_from_location(enemy_king);
+
+    let from_bit = 1u64 << chess_move.from_square();
+    let mut occupied = board.occupied() & !from_bit;
+    // the piece that ends up giving a direct check, and the square it is on
+    let (checking_piece, checking_square, moved_bits) =
+        if let Some((king_to, rook_to)) = castling_destinations(chess_move) {
+            let rook_bit = 1u64 << chess_move.to_square();
+            occupied = (occupied & !rook_bit) | square_bit(king_to) | square_bit(rook_to);
+            (ROOK, square_from_location(rook_to), from_bit | rook_bit)
+        } else {
+            if chess_move.is_en_passant() {
+                occupied &= !square_bit((chess_move.from().0, chess_move.to().1));
+            }
+            occupied |= 1u64 << chess_move.to_square();
+            let piece = chess_move
+                .promotion_to()
+                .unwrap_or(board.get_piece(chess_move.from()));
+            (piece, chess_move.to_square(), from_bit)
+        };
+
+    let direct = attacks::piece_attacks(checking_piece, checking_square, side, occupied);
+    if direct & (1u64 << enemy_king_square) != EMPTY_BITBOARD {
+        return true;
+    }
+
+    // any of our other sliders that now see the king had the moving piece in the way
+    let sliders_seeing_king = (attacks::bishop_attacks(enemy_king_square, occupied)
+        & (board.piece_bitboard(BISHOP, side) | board.piece_bitboard(QUEEN, side)))
+        | (attacks::rook_attacks(enemy_king_square, occupied)
+            & (board.piece_bitboard(ROOK, side) | board.piece_bitboard(QUEEN, side)));
+    return sliders_seeing_king & !moved_bits != EMPTY_BITBOARD;
+}
+
+fn is_king_move_legal(
+    board: &Board,
+    king_move: Move,
+    king_square: usize,
+    enemy_colour: i8,
+) -> bool {
+    if let Some((king_to, _)) = castling_destinations(king_move) {
+        // no square the king crosses or lands on can be attacked. the rook leaves its square
+        // too, which can open a line onto the king in chess960
+        let occupied = castling_blockers(board, king_move.from(), king_move.to());
+        let king_path = attacks::squares_between(king_square, square_from_location(king_to))
+            | square_bit(king_to);
+
+        return Squares(king_path)
+            .all(|square| attackers_to(board, square, enemy_colour, occupied) == EMPTY_BITBOARD);
+    }
+
+    // take the king off the board so it can't hide behind itself from a slider
+    let occupied = board.occupied() & !(1u64 << king_square);
+
+    return attackers_to(board, king_move.to_square(), enemy_colour, occupied) == EMPTY_BITBOARD;
+}
+
+fn is_en_passant_legal(
+    board: &Board,
+    en_passant_move: Move,
+    king_square: usize,
+    enemy_colour: i8,
+) -> bool {
+    // two pawns leave the same rank at once, which can uncover a check no mask would catch,
+    // so check the king against the board as it will be after the capture
+    let from = en_passant_move.from();
+    let captured_square = square_from_location((from.0, en_passant_move.to().1));
+    let occupied = (board.occupied() & !square_bit(from) & !(1u64 << captured_square))
+        | (1u64 << en_passant_move.to_square());
+
+    let attackers = attackers_to(board, king_square, enemy_colour, occupied);
+
+    return attackers & !(1u64 << captured_square) == EMPTY_BITBOARD;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::conversion;
+    use crate::movegen::*;
+
+    fn legal_move_notations(fen: &str) -> Vec<String> {
+        let board = conversion::convert_fen_to_board(fen);
+        return generate_legal_moves(&board)
+            .iter()
+            .map(|m| conversion::convert_move_to_notation(&m))
+            .collect();
+    }
+
+    #[test]
+    fn move_queries_agree_with_the_generators() {
+        let mut checks_found = 0;
+        let mut fens: Vec<&str> = crate::constants::BENCH_FENS
+            .iter()
+            .step_by(5)
+            .copied()
+            .collect();
+        // en passant, castling both ways, promotions and a chess960 position
+        fens.push("r3k2r/p2pqpb1/bn2pnp1/2pP4/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq c6 0 2");
+        fens.push("2r1k3/1P6/8/8/8/8/1p6/R3K2R b KQ - 0 1");
+        fens.push("8/8/8/K2pP2r/8/8/8/7k w - d6 0 2");
+        fens.push("1rqbkrbn/1ppppp1p/1n6/p1N3p1/8/2P4P/PP1PPPP1/1RQBKRBN w FBfb - 0 9");
+
+        for fen in fens {
+            let board = conversion::convert_fen_to_board(fen);
+            let side = board.side_to_move;
+            let in_check = crate::evaluate::is_in_check(&board, side, None);
+            let pseudo_legal_moves = generate_pseudo_legal_moves(&board, side, in_check);
+            let legal_moves = generate_legal_moves(&board);
+
+            for bits in 0..=u16::MAX {
+                let chess_move = Move(bits);
+                let is_pseudo_legal_move = pseudo_legal_moves.contains(chess_move);
+                assert_eq!(
+                    is_pseudo_legal(&board, chess_move),
+                    is_pseudo_legal_move,
+                    "{} {:?}",
+                    fen,
+                    chess_move
+                );
+                assert_eq!(
+                    is_legal(&board, chess_move),
+                    legal_moves.contains(chess_move),
+                    "{} {:?}",
+                    fen,
+                    chess_move
+                );
+
+                if is_pseudo_legal_move {
+                    let mut after = board.clone();
+                    after.make_move(chess_move);
+                    let checks = crate::evaluate::is_in_check(&after, -side, None);
+                    assert_eq!(
+                        gives_check(&board, chess_move),
+                        checks,
+                        "{} {:?}",
+                        fen,
+                        chess_move
+                    );
+                    checks_found += checks as usize;
+                }
+            }
+        }
+        assert!(checks_found > 20);
+    }
+
+    #[test]
+    fn pawns_on_their_last_rank_have_no_moves() {
+        let board = conversion::convert_fen_to_board("P3k3/8/8/8/8/8/8/4K3 w - - 0 1");
+        assert_eq!(generate_legal_moves(&board).len(), 5);
+
+        let board = conversion::convert_fen_to_board("4k3/8/8/8/8/8/8/p3K3 b - - 0 1");
+        assert_eq!(generate_legal_moves(&board).len(), 5);
+    }
+
+    #[test]
+    fn en_passant_cannot_uncover_check_along_rank() {
+        let moves = legal_move_notations("8/8/8/KPp4r/8/8/8/7k w - c6 0 2");
+        assert!(!moves.contains(&"b5c6".to_string()));
+        assert!(moves.contains(&"b5b6".to_string()));
+    }
+
+    #[test]
+    fn en_passant_can_capture_checking_pawn() {
+        let moves = legal_move_notations("8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1");
+        assert!(moves.contains(&"e4d3".to_string()));
+    }
+
+    #[test]
+    fn cannot_castle_through_attacked_square() {
+        let moves = legal_move_notations("4kr2/8/8/8/8/8/8/R3K2R w KQ - 0 1");
+        assert!(!moves.contains(&"e1g1".to_string()));
+        assert!(moves.contains(&"e1c1".to_string()));
+    }
+
+    #[test]
+    fn pinned_piece_only_moves_along_pin() {
+        let moves = legal_move_notations("4k3/4r3/8/8/8/8/4R3/4K3 w - - 0 1");
+        assert!(moves.contains(&"e2e7".to_string()));
+        assert!(moves.contains(&"e2e5".to_string()));
+        assert!(!moves.contains(&"e2d2".to_string()));
+    }
+
+    #[test]
+    fn captures_and_quiets_split_every_legal_move() {
+        for fen in crate::constants::BENCH_FENS {
+            let board = conversion::convert_fen_to_board(fen);
+            let all_moves = generate_legal_moves(&board);
+
+            let mut split_moves = MoveList::new();
+            generate_legal_moves_of_type(&board, MoveType::Captures, &mut split_moves);
+            let capture_count = split_moves.len();
+            generate_legal_moves_of_type(&board, MoveType::Quiets, &mut split_moves);
+
+            assert_eq!(split_moves.len(), all_moves.len(), "{}", fen);
+            assert!(all_moves.iter().all(|m| split_moves.contains(m)), "{}", fen);
+            assert!(
+                split_moves
+                    .iter()
+                    .take(capture_count)
+                    .all(|m| m.is_capture() || m.promotion_to() == Some(QUEEN)),
+                "{}",
+                fen
+            );
+        }
+    }
+
+    #[test]
+    fn evasions_match_legal_moves_when_in_check() {
+        // every position one move on from the bench positions that leaves the other side in check
+        let mut checks_seen = 0;
+        for fen in crate::constants::BENCH_FENS {
+            let mut board = conversion::convert_fen_to_board(fen);
+            for chess_move in generate_legal_moves(&board).iter() {
+                board.make_move(chess_move);
+                if crate::evaluate::is_in_check(&board, board.side_to_move, None) {
+                    checks_seen += 1;
+                    let legal_moves = generate_legal_moves(&board);
+                    let evasions = generate_evasions(&board);
+
+                    assert_eq!(
+                        evasions.len(),
+                        legal_moves.len(),
+                        "{} {:?}",
+                        fen,
+                        chess_move
+                    );
+                    assert!(legal_moves.iter().all(|m| evasions.contains(m)), "{}", fen);
+                }
+                board.un_make_move(chess_move);
+            }
+        }
+        assert!(checks_seen > 50);
+    }
+
+    #[test]
+    fn double_check_only_allows_king_moves() {
+        let moves = legal_move_notations("4k3/8/8/8/1b6/8/7R/r3K3 w - - 0 1");
+        assert!(moves.iter().all(|m| m.starts_with("e1")));
+    }
+}
@@ -0,0 +1,258 @@
+//! hands the search its moves one at a time, best guesses first. each group of moves is only
+//! generated and scored once the search gets to it, so a cutoff early on saves the rest of the work
+use crate::bitboard::*;
+use crate::board::Board;
+use crate::constants::*;
+use crate::evaluate::get_piece_value;
+use crate::movegen::{self, MoveType};
+use crate::moves::*;
+use crate::see::see_ge;
+
+/// indexed by colour index, from square and to square
+pub type HistoryTable = [[[i32; 64]; 64]; 2];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Stage {
+    HashMove,
+    GenerateCaptures,
+    GoodCaptures,
+    Refutations,
+    Quiets,
+    BadCaptures,
+    Done,
+}
+
+pub struct MovePicker {
+    stage: Stage,
+    hash_move: Move,
+    /// the killers then the countermove, quiet moves that cut off somewhere similar before
+    refutations: [Move; 3],
+    refutation_index: usize,
+    captures: MoveList,
+    capture_index: usize,
+    bad_captures: MoveList,
+    bad_capture_index: usize,
+    quiets: MoveList,
+    quiet_index: usize,
+    captures_only: bool,
+}
+
+impl MovePicker {
+    pub fn new(hash_move: Move, killer_moves: [Move; 2], counter_move: Move) -> MovePicker {
+        return MovePicker {
+            stage: Stage::HashMove,
+            hash_move,
+            refutations: [killer_moves[0], killer_moves[1], counter_move],
+            refutation_index: 0,
+            captures: MoveList::new(),
+            capture_index: 0,
+            bad_captures: MoveList::new(),
+            bad_capture_index: 0,
+            quiets: MoveList::new(),
+            quiet_index: 0,
+            captures_only: false,
+        };
+    }
+
+    /// only the captures that don't lose material, for the quiescence search
+    pub fn new_for_quiescence() -> MovePicker {
+        let mut move_picker = MovePicker::new(Move::NULL, [Move::NULL; 2], Move::NULL);
+        move_picker.stage = Stage::GenerateCaptures;
+        move_picker.captures_only = true;
+        return move_picker;
+    }
+
+    pub fn next_move(&mut self, board: &Board, history: &HistoryTable) -> Option<Move> {
+        loop {
+            match self.stage {
+                Stage::HashMove => {
+                    self.stage = Stage::GenerateCaptures;
+                    if self.is_hash_move_usable(board) {
+                        return Some(self.hash_move);
+                    }
+                }
+                Stage::GenerateCaptures => {
+                    self.captures = movegen::generate_captures(board);
+                    score_captures(board, &mut self.captures);
+                    self.stage = Stage::GoodCaptures;
+                }
+                Stage::GoodCaptures => {
+                    while self.capture_index < self.captures.len() {
+                        let chess_move = pick_best(&mut self.captures, self.capture_index);
+                        self.capture_index += 1;
+
+                        if chess_move == self.hash_move {
+                            continue;
+                        }
+                        // captures that lose material wait until the quiets have had a go
+                        if !see_ge(board, chess_move, 0) {
+                            self.bad_captures.push(chess_move);
+                            continue;
+                        }
+                        return Some(chess_move);
+                    }
+
+                    // the quiescence search doesn't look at captures that lose material at all
+                    if self.captures_only {
+                        self.stage = Stage::Done;
+                    } else {
+                        // the refutations are only played if they are legal quiets here
+                        movegen::generate_legal_moves_of_type(
+                            board,
+                            MoveType::Quiets,
+                            &mut self.quiets,
+                        );
+                        self.stage = Stage::Refutations;
+                    }
+                }
+                Stage::Refutations => {
+                    while self.refutation_index < self.refutations.len() {
+                        let chess_move = self.refutations[self.refutation_index];
+                        self.refutation_index += 1;
+
+                        if chess_move.is_null()
+                            || chess_move == self.hash_move
+                            || self.refutations[..self.refutation_index - 1].contains(&chess_move)
+                            || !self.quiets.contains(chess_move)
+                        {
+                            continue;
+                        }
+                        return Some(chess_move);
+                    }
+
+                    score_quiets(board, &mut self.quiets, history);
+                    self.stage = Stage::Quiets;
+                }
+                Stage::Quiets => {
+                    while self.quiet_index < self.quiets.len() {
+                        let chess_move = pick_best(&mut self.quiets, self.quiet_index);
+                        self.quiet_index += 1;
+
+                        if chess_move == self.hash_move || self.refutations.contains(&chess_move) {
+                            continue;
+                        }
+                        return Some(chess_move);
+                    }
+                    self.stage = Stage::BadCaptures;
+                }
+                Stage::BadCaptures => {
+                    while self.bad_capture_index < self.bad_captures.len() {
+                        let chess_move = self.bad_captures.get(self.bad_capture_index);
+                        self.bad_capture_index += 1;
+
+                        if chess_move == self.hash_move {
+                            continue;
+                        }
+                        return Some(chess_move);
+                    }
+                    self.stage = Stage::Done;
+                }
+                Stage::Done => return None,
+            }
+        }
+    }
+
+    /// the hash move comes from a position with the same zobrist key, so it is almost certainly
+    /// legal here. a key collision can still hand us anything, so check before trusting it
+    fn is_hash_move_usable(&self, board: &Board) -> bool {
+        return !self.hash_move.is_null() && board.is_legal(self.hash_move);
+    }
+}
+
+/// most valuable victim first, then least valuable attacker
+fn score_captures(board: &Board, moves: &mut MoveList) {
+    for scored_move in moves.scored_moves_mut() {
+        let chess_move = scored_move.chess_move;
+        let victim = if chess_move.is_en_passant() {
+            PAWN
+        } else {
+            board.get_piece(chess_move.to())
+        };
+        let attacker = board.get_piece(chess_move.from());
+
+        scored_move.score = get_piece_value(victim) * 8 - attacker as i32
+            + chess_move.promotion_to().map_or(0, get_piece_value);
+    }
+}
+
+fn score_quiets(board: &Board, moves: &mut MoveList, history: &HistoryTable) {
+    let side = colour_index(board.side_to_move);
+    for scored_move in moves.scored_moves_mut() {
+        let chess_move = scored_move.chess_move;
+        scored_move.score = history[side][chess_move.from_square()][chess_move.to_square()];
+    }
+}
+
+/// swap the best scoring move from `index` onwards into `index` and return it
+fn pick_best(moves: &mut MoveList, index: usize) -> Move {
+    let scored_moves = moves.scored_moves_mut();
+    let mut best_index = index;
+    for candidate in index + 1..scored_moves.len() {
+        if scored_moves[candidate].score > scored_moves[best_index].score {
+            best_index = candidate;
+        }
+    }
+    scored_moves.swap(index, best_index);
+    return scored_moves[index].chess_move;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::BENCH_FENS;
+    use crate::conversion;
+    use crate::movegen;
+    use crate::movepick::*;
+
+    fn picked_moves(fen: &str, hash_move: &str, killer: &str) -> Vec<Move> {
+        let board = conversion::convert_fen_to_board(fen);
+        let history: HistoryTable = [[[0; 64]; 64]; 2];
+        let hash_move = board
+            .convert_notation_to_move(hash_move.to_string())
+            .unwrap();
+        let killer = board.convert_notation_to_move(killer.to_string()).unwrap();
+
+        let mut move_picker = MovePicker::new(hash_move, [killer, Move::NULL], Move::NULL);
+        let mut moves = vec![];
+        while let Some(chess_move) = move_picker.next_move(&board, &history) {
+            moves.push(chess_move);
+        }
+        return moves;
+    }
+
+    #[test]
+    fn picks_every_legal_move_exactly_once() {
+        for fen in BENCH_FENS.iter().take(10) {
+            let board = conversion::convert_fen_to_board(fen);
+            let legal_moves = movegen::generate_legal_moves(&board);
+            let hash_move = legal_moves.get(legal_moves.len() / 2);
+            let history: HistoryTable = [[[0; 64]; 64]; 2];
+
+            let mut move_picker = MovePicker::new(hash_move, [Move::NULL; 2], Move::NULL);
+            let mut picked = MoveList::new();
+            while let Some(chess_move) = move_picker.next_move(&board, &history) {
+                assert!(!picked.contains(chess_move), "{} picked twice", fen);
+                picked.push(chess_move);
+            }
+
+            assert_eq!(picked.len(), legal_moves.len(), "{}", fen);
+            assert!(legal_moves.iter().all(|m| picked.contains(m)), "{}", fen);
+        }
+    }
+
+    #[test]
+    fn stages_come_in_order() {
+        // hash move, then the free rook least valuable attacker first, then the killer, then the
+        // quiets, then the pawn that costs the queen
+        let moves = picked_moves("3qk3/8/8/3p4/2Q5/8/r7/R3K3 w - - 0 1", "e1f1", "e1d1");
+        let notations: Vec<String> = moves
+            .iter()
+            .map(conversion::convert_move_to_notation)
+            .collect();
+
+        assert_eq!(notations[0], "e1f1");
+        assert_eq!(notations[1], "a1a2");
+        assert_eq!(notations[2], "c4a2");
+        assert_eq!(notations[3], "e1d1");
+        assert_eq!(notations.last().unwrap(), "c4d5");
+    }
+}
@@ -1,38 +1,253 @@
-use crate::constants::EMPTY;
-
-#[derive(Clone, Debug)]
-pub struct Move {
-    pub from: (usize, usize),
-    pub from_piece: i8,
-    pub from_colour: i8,
-    pub to: (usize, usize),
-    pub to_piece: i8,
-    pub to_colour: i8,
-    pub promotion_to: Option<i8>,
-    pub en_passant: bool,
-    pub castle_from_to_square: Option<((usize, usize), (usize, usize))>,
-    pub castling_intermediary_square: Option<(usize, usize)>,
-    pub sort_score: u8,
-    pub search_score: i32,
-    pub illegal_move: bool,
-}
-impl Default for Move {
+use crate::bitboard::*;
+use crate::constants::*;
+use std::fmt;
+
+// move flags, stored in the top four bits of a move
+pub const QUIET_MOVE: u16 = 0;
+pub const DOUBLE_PAWN_PUSH: u16 = 1;
+pub const KING_CASTLE: u16 = 2;
+pub const QUEEN_CASTLE: u16 = 3;
+pub const CAPTURE: u16 = 4;
+pub const EN_PASSANT_CAPTURE: u16 = 5;
+pub const KNIGHT_PROMOTION: u16 = 8;
+pub const BISHOP_PROMOTION: u16 = 9;
+pub const ROOK_PROMOTION: u16 = 10;
+pub const QUEEN_PROMOTION: u16 = 11;
+pub const KNIGHT_PROMOTION_CAPTURE: u16 = 12;
+pub const BISHOP_PROMOTION_CAPTURE: u16 = 13;
+pub const ROOK_PROMOTION_CAPTURE: u16 = 14;
+pub const QUEEN_PROMOTION_CAPTURE: u16 = 15;
+
+const CAPTURE_BIT: u16 = 0b0100;
+const PROMOTION_BIT: u16 = 0b1000;
+
+/// a move packed into 16 bits: from square, to square and a flag.
+/// everything needed to undo it lives on the board's ply record, not here
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Move(pub u16);
+
+impl Move {
+    /// a8a8, which no real move can be
+    pub const NULL: Move = Move(0);
+
+    pub fn new(from: (usize, usize), to: (usize, usize), flag: u16) -> Move {
+        return Move(
+            square_from_location(from) as u16 | (square_from_location(to) as u16) << 6 | flag << 12,
+        );
+    }
+
+    pub fn from_square(self) -> usize {
+        return (self.0 & 0x3F) as usize;
+    }
+    pub fn to_square(self) -> usize {
+        return ((self.0 >> 6) & 0x3F) as usize;
+    }
+    pub fn from(self) -> (usize, usize) {
+        return location_from_square(self.from_square());
+    }
+    pub fn to(self) -> (usize, usize) {
+        return location_from_square(self.to_square());
+    }
+    pub fn flag(self) -> u16 {
+        return self.0 >> 12;
+    }
+
+    pub fn is_null(self) -> bool {
+        return self == Move::NULL;
+    }
+    pub fn is_capture(self) -> bool {
+        return self.flag() & CAPTURE_BIT != 0;
+    }
+    pub fn is_en_passant(self) -> bool {
+        return self.flag() == EN_PASSANT_CAPTURE;
+    }
+    pub fn is_double_pawn_push(self) -> bool {
+        return self.flag() == DOUBLE_PAWN_PUSH;
+    }
+    pub fn is_castle(self) -> bool {
+        return self.flag() == KING_CASTLE || self.flag() == QUEEN_CASTLE;
+    }
+    pub fn is_promotion(self) -> bool {
+        return self.flag() & PROMOTION_BIT != 0;
+    }
+
+    pub fn promotion_to(self) -> Option<i8> {
+        if !self.is_promotion() {
+            return None;
+        }
+        return Some(match self.flag() & 0b11 {
+            0 => KNIGHT,
+            1 => BISHOP,
+            2 => ROOK,
+            _ => QUEEN,
+        });
+    }
+}
+
+/// the flag for a pawn promoting to the piece, with or without a capture
+pub fn promotion_flag(piece: i8, is_capture: bool) -> u16 {
+    let flag = match piece {
+        KNIGHT => KNIGHT_PROMOTION,
+        BISHOP => BISHOP_PROMOTION,
+        ROOK => ROOK_PROMOTION,
+        _ => QUEEN_PROMOTION,
+    };
+    return if is_capture { flag | CAPTURE_BIT } else { flag };
+}
+
+/// why a move typed in or read from a file couldn't be turned into a legal move
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MoveParseError {
+    /// not written like a move at all
+    Malformed(String),
+    /// reads fine, but no legal move matches it
+    Illegal(String),
+    /// more than one legal move matches it
+    Ambiguous(String),
+}
+
+impl fmt::Display for MoveParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveParseError::Malformed(text) => write!(f, "malformed move '{}'", text),
+            MoveParseError::Illegal(text) => write!(f, "illegal move '{}'", text),
+            MoveParseError::Ambiguous(text) => write!(f, "ambiguous move '{}'", text),
+        }
+    }
+}
+
+/// more than any legal position can have
+pub const MAX_MOVES: usize = 256;
+
+#[derive(Clone, Copy, Default)]
+pub struct ScoredMove {
+    pub chess_move: Move,
+    pub score: i32,
+}
+
+/// fixed capacity list of moves with a score each for ordering, so generating doesn't allocate
+#[derive(Clone)]
+pub struct MoveList {
+    moves: [ScoredMove; MAX_MOVES],
+    len: usize,
+}
+
+impl Default for MoveList {
     fn default() -> Self {
-        // return a default instance of Move
-        return Move {
-            from: (0, 0),
-            from_piece: EMPTY,
-            from_colour: EMPTY,
-            to: (0, 0),
-            to_piece: EMPTY,
-            to_colour: EMPTY,
-            promotion_to: None,
-            en_passant: false,
-            castle_from_to_square: None,
-            castling_intermediary_square: None,
-            sort_score: 0,
-            search_score: 0,
-            illegal_move: false,
+        Self::new()
+    }
+}
+
+impl MoveList {
+    pub fn new() -> MoveList {
+        return MoveList {
+            moves: [ScoredMove::default(); MAX_MOVES],
+            len: 0,
         };
     }
+
+    pub fn push(&mut self, chess_move: Move) {
+        self.moves[self.len] = ScoredMove {
+            chess_move,
+            score: 0,
+        };
+        self.len += 1;
+    }
+
+    pub fn len(&self) -> usize {
+        return self.len;
+    }
+    pub fn is_empty(&self) -> bool {
+        return self.len == 0;
+    }
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    pub fn get(&self, index: usize) -> Move {
+        return self.moves[index].chess_move;
+    }
+    pub fn contains(&self, chess_move: Move) -> bool {
+        return self.iter().any(|m| m == chess_move);
+    }
+
+    pub fn scored_moves(&self) -> &[ScoredMove] {
+        return &self.moves[..self.len];
+    }
+    pub fn scored_moves_mut(&mut self) -> &mut [ScoredMove] {
+        return &mut self.moves[..self.len];
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Move> + '_ {
+        return self.scored_moves().iter().map(|scored| scored.chess_move);
+    }
+
+    /// keep only the moves the predicate accepts, in their current order
+    pub fn retain(&mut self, keep: impl FnMut(Move) -> bool) {
+        self.retain_from(0, keep);
+    }
+
+    /// like retain, but leaves the moves before `start` alone
+    pub fn retain_from(&mut self, start: usize, mut keep: impl FnMut(Move) -> bool) {
+        let mut kept = start;
+        for index in start..self.len {
+            if keep(self.moves[index].chess_move) {
+                self.moves[kept] = self.moves[index];
+                kept += 1;
+            }
+        }
+        self.len = kept;
+    }
+
+    /// highest score first. insertion sort keeps equal scores in generation order
+    pub fn sort_by_score(&mut self) {
+        for index in 1..self.len {
+            let current = self.moves[index];
+            let mut position = index;
+            while position > 0 && self.moves[position - 1].score < current.score {
+                self.moves[position] = self.moves[position - 1];
+                position -= 1;
+            }
+            self.moves[position] = current;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::constants::*;
+    use crate::moves::*;
+
+    #[test]
+    fn move_packs_squares_and_flag() {
+        let chess_move = Move::new((1, 4), (0, 3), QUEEN_PROMOTION_CAPTURE);
+        assert_eq!(chess_move.from(), (1, 4));
+        assert_eq!(chess_move.to(), (0, 3));
+        assert!(chess_move.is_capture());
+        assert_eq!(chess_move.promotion_to(), Some(QUEEN));
+
+        let castle = Move::new((7, 4), (7, 6), KING_CASTLE);
+        assert!(castle.is_castle());
+        assert!(!castle.is_capture());
+        assert_eq!(castle.promotion_to(), None);
+
+        assert!(Move::new((3, 4), (2, 3), EN_PASSANT_CAPTURE).is_capture());
+    }
+
+    #[test]
+    fn move_list_sorts_highest_score_first_and_keeps_ties_in_order() {
+        let mut moves = MoveList::new();
+        for column in 0..4 {
+            moves.push(Move::new((6, column), (5, column), QUIET_MOVE));
+        }
+        moves.scored_moves_mut()[2].score = 10;
+        moves.sort_by_score();
+
+        let order: Vec<usize> = moves.iter().map(|m| m.from().1).collect();
+        assert_eq!(order, vec![2, 0, 1, 3]);
+
+        moves.retain(|m| m.from().1 != 0);
+        assert_eq!(moves.len(), 3);
+        assert!(!moves.contains(Move::new((6, 0), (5, 0), QUIET_MOVE)));
+    }
 }
@@ -0,0 +1,335 @@
+//! runs perft suites: epd lines giving a position and the node count expected at each depth,
+//! like `rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 ;D1 20 ;D2 400`
+use crate::board::{Board, ValidationMode};
+use crate::movegen;
+use crate::moves::Move;
+use crate::search::SearchEngine;
+use std::io::BufRead;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::thread;
+use std::time::Instant;
+
+/// how deep a suite is checked when no depth is given
+pub const DEFAULT_PERFT_SUITE_DEPTH: i8 = 4;
+/// the size of the perft hash table when threads are asked for without a hash size
+pub const DEFAULT_PERFT_HASH_MEGABYTES: usize = 16;
+
+/// subtree counts by position and depth, shared between the perft threads without locking.
+/// each entry is the key xored with the data next to the data, so an entry torn by two
+/// threads writing at once just reads as a miss
+pub struct PerftHashTable {
+    entries: Vec<[AtomicU64; 2]>,
+}
+
+impl PerftHashTable {
+    /// as many entries as fit in the size, rounded down to a power of two
+    pub fn new(megabytes: usize) -> PerftHashTable {
+        let entry_size = std::mem::size_of::<[AtomicU64; 2]>();
+        let wanted_entries = std::cmp::max(1, megabytes * 1024 * 1024 / entry_size);
+        let entry_count = 1usize << wanted_entries.ilog2();
+        return PerftHashTable {
+            entries: (0..entry_count)
+                .map(|_| [AtomicU64::new(0), AtomicU64::new(0)])
+                .collect(),
+        };
+    }
+
+    fn entry(&self, zobrist_key: u64) -> &[AtomicU64; 2] {
+        return &self.entries[zobrist_key as usize & (self.entries.len() - 1)];
+    }
+
+    /// the node count stored for the position at exactly this depth
+    pub fn probe(&self, zobrist_key: u64, depth: i8) -> Option<u64> {
+        let [checked_key, data] = self.entry(zobrist_key);
+        let data = data.load(Ordering::Relaxed);
+        let checked_key = checked_key.load(Ordering::Relaxed);
+
+        if checked_key ^ data != zobrist_key || data & 0xFF != depth as u64 {
+            return None;
+        }
+        return Some(data >> 8);
+    }
+
+    /// always replaces whatever was in the slot
+    pub fn store(&self, zobrist_key: u64, depth: i8, nodes: u64) {
+        let data = (nodes << 8) | depth as u64;
+        let [checked_key, stored_data] = self.entry(zobrist_key);
+        checked_key.store(zobrist_key ^ data, Ordering::Relaxed);
+        stored_data.store(data, Ordering::Relaxed);
+    }
+}
+
+/// the leaf count below the position, reusing counts from the table when there is one
+pub fn cached_perft(board: &mut Board, depth: i8, table: Option<&PerftHashTable>) -> u64 {
+    if depth <= 0 {
+        return 1;
+    }
+    if let Some(nodes) = table.and_then(|table| table.probe(board.zobrist_key, depth)) {
+        return nodes;
+    }
+
+    let moves = movegen::generate_legal_moves(board);
+    // every legal move is one leaf, no need to make them
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    let mut nodes = 0;
+    for generated_move in moves.iter() {
+        board.make_move(generated_move);
+        nodes += cached_perft(board, depth - 1, table);
+        board.un_make_move(generated_move);
+    }
+
+    if let Some(table) = table {
+        table.store(board.zobrist_key, depth, nodes);
+    }
+    return nodes;
+}
+
+/// the leaf count below each root move, in the order the moves are generated. the threads take
+/// the next root move that nobody has started on until there are none left
+pub fn parallel_perft(
+    board: &Board,
+    depth: i8,
+    threads: usize,
+    table: Option<&PerftHashTable>,
+) -> Vec<(Move, u64)> {
+    if depth <= 0 {
+        return Vec::new();
+    }
+
+    let root_moves: Vec<Move> = movegen::generate_legal_moves(board).iter().collect();
+    let counts: Vec<AtomicU64> = root_moves.iter().map(|_| AtomicU64::new(0)).collect();
+    let next_root_move = AtomicUsize::new(0);
+
+    thread::scope(|scope| {
+        for _ in 0..std::cmp::max(1, threads) {
+            scope.spawn(|| {
+                let mut board = board.clone();
+                loop {
+                    let index = next_root_move.fetch_add(1, Ordering::Relaxed);
+                    let Some(&root_move) = root_moves.get(index) else {
+                        break;
+                    };
+                    board.make_move(root_move);
+                    let nodes = cached_perft(&mut board, depth - 1, table);
+                    board.un_make_move(root_move);
+                    counts[index].store(nodes, Ordering::Relaxed);
+                }
+            });
+        }
+    });
+
+    return root_moves
+        .into_iter()
+        .zip(counts.iter().map(|count| count.load(Ordering::Relaxed)))
+        .collect();
+}
+
+pub struct PerftPosition {
+    pub fen: String,
+    pub board: Board,
+    /// the depth and the node count expected there, in the order they were written
+    pub expected_nodes: Vec<(i8, i128)>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct PerftSuiteSummary {
+    pub passed: usize,
+    pub failed: usize,
+}
+
+/// a position and its ;Dn counts. the fen can leave out its clocks, but has to be a position
+/// that can be played from
+pub fn parse_perft_line(line: &str) -> Result<PerftPosition, String> {
+    let mut fields = line.split(';');
+    let fen = fields.next().unwrap_or_default().trim();
+    let mut board =
+        Board::from_fen(fen).map_err(|error| format!("invalid fen '{}': {}", fen, error))?;
+    board
+        .validate(ValidationMode::Lenient)
+        .map_err(|problems| format!("invalid position '{}': {:?}", fen, problems))?;
+
+    let mut expected_nodes = Vec::new();
+    for field in fields.map(str::trim).filter(|field| !field.is_empty()) {
+        let parsed = field
+            .strip_prefix('D')
+            .and_then(|counts| counts.split_once(char::is_whitespace))
+            .and_then(|(depth, nodes)| {
+                Some((
+                    depth.parse::<i8>().ok()?,
+                    nodes.trim().parse::<i128>().ok()?,
+                ))
+            });
+        let Some(depth_and_nodes) = parsed else {
+            return Err(format!("invalid perft count ';{}'", field));
+        };
+        expected_nodes.push(depth_and_nodes);
+    }
+
+    return Ok(PerftPosition {
+        fen: fen.to_string(),
+        board,
+        expected_nodes,
+    });
+}
+
+/// checks every position in the suite up to the maximum depth, printing a line for each one.
+/// a position stops at the first depth that doesn't match, and a line that can't be read fails
+/// on its own
+pub fn run_perft_suite<R: BufRead>(suite: R, max_depth: i8) -> Result<PerftSuiteSummary, String> {
+    let mut engine = SearchEngine::new();
+    let mut summary = PerftSuiteSummary::default();
+    let suite_start = Instant::now();
+
+    for (line_number, line) in suite.lines().enumerate() {
+        let line = line.map_err(|error| error.to_string())?;
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut position = match parse_perft_line(&line) {
+            Ok(position) => position,
+            Err(error) => {
+                summary.failed += 1;
+                println!("FAIL line {}: {}", line_number + 1, error);
+                continue;
+            }
+        };
+        let board = &mut position.board;
+        let start = Instant::now();
+        let mut mismatch = None;
+        let mut deepest = 0;
+        for &(depth, expected) in position.expected_nodes.iter() {
+            if depth > max_depth {
+                continue;
+            }
+            let nodes = engine.perft(board, depth, false);
+            if nodes != expected {
+                mismatch = Some((depth, expected, nodes));
+                break;
+            }
+            deepest = std::cmp::max(deepest, depth);
+        }
+
+        let seconds = start.elapsed().as_secs_f32();
+        match mismatch {
+            None => {
+                summary.passed += 1;
+                println!("pass {} depth {} in {:.3}s", position.fen, deepest, seconds);
+            }
+            Some((depth, expected, nodes)) => {
+                summary.failed += 1;
+                println!(
+                    "FAIL {} depth {}: expected {} got {} in {:.3}s",
+                    position.fen, depth, expected, nodes, seconds
+                );
+            }
+        }
+    }
+
+    println!(
+        "{} passed, {} failed in {:.3}s",
+        summary.passed,
+        summary.failed,
+        suite_start.elapsed().as_secs_f32()
+    );
+    return Ok(summary);
+}
+
+/// `perftsuite <file> [max depth]`, from the console or the command line
+pub fn perft_suite_command<'a>(
+    mut arguments: impl Iterator<Item = &'a str>,
+) -> Result<PerftSuiteSummary, String> {
+    let Some(path) = arguments.next() else {
+        return Err("perftsuite needs a file name".to_string());
+    };
+    let max_depth = match arguments.next() {
+        Some(depth) => depth
+            .parse::<i8>()
+            .map_err(|_| format!("invalid depth '{}'", depth))?,
+        None => DEFAULT_PERFT_SUITE_DEPTH,
+    };
+
+    let file = std::fs::File::open(path).map_err(|error| format!("{}: {}", path, error))?;
+    return run_perft_suite(std::io::BufReader::new(file), max_depth);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::perft::*;
+
+    #[test]
+    fn reads_perft_lines() {
+        let position = parse_perft_line("8/8/8/8/8/8/8/K1k5 w - - ;D1 3 ;D2 9").unwrap();
+        assert_eq!(position.fen, "8/8/8/8/8/8/8/K1k5 w - -");
+        assert_eq!(position.expected_nodes, vec![(1, 3), (2, 9)]);
+
+        assert!(parse_perft_line("8/8/8/8/8/8/8/K1k5 w - - ;D1 three").is_err());
+        assert!(parse_perft_line("8/8/8/8/8/8/8/K1k5 w - - ;X1 3").is_err());
+        assert!(parse_perft_line("not a fen ;D1 20").is_err());
+        assert!(parse_perft_line("P3k3/8/8/8/8/8/8/4K3 w - - ;D1 5").is_err());
+    }
+
+    #[test]
+    fn perft_suite_passes_to_depth_three() {
+        let summary = run_perft_suite(include_str!("../perft.epd").as_bytes(), 3).unwrap();
+        assert_eq!(summary.failed, 0);
+        assert!(summary.passed > 0);
+    }
+
+    #[test]
+    fn unreadable_lines_fail_on_their_own() {
+        let suite = "P3k3/8/8/8/8/8/8/4K3 w - - 0 1 ;D1 5\n\
+                     rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 ;D1 20\n";
+        let summary = run_perft_suite(suite.as_bytes(), 3).unwrap();
+        assert_eq!(
+            summary,
+            PerftSuiteSummary {
+                passed: 1,
+                failed: 1
+            }
+        );
+    }
+
+    #[test]
+    fn wrong_counts_fail() {
+        let suite = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 ;D1 20 ;D2 401\n";
+        let summary = run_perft_suite(suite.as_bytes(), 3).unwrap();
+        assert_eq!(
+            summary,
+            PerftSuiteSummary {
+                passed: 0,
+                failed: 1
+            }
+        );
+    }
+
+    #[test]
+    fn hash_table_only_answers_for_the_same_depth() {
+        let table = PerftHashTable::new(1);
+        table.store(0x1234_5678_9ABC_DEF0, 3, 97862);
+        assert_eq!(table.probe(0x1234_5678_9ABC_DEF0, 3), Some(97862));
+        assert_eq!(table.probe(0x1234_5678_9ABC_DEF0, 4), None);
+        assert_eq!(table.probe(0x1234_5678_9ABC_DEF1, 3), None);
+    }
+
+    #[test]
+    fn parallel_cached_perft_matches_the_uncached_counts() {
+        let board =
+            Board::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+                .unwrap();
+        let table = PerftHashTable::new(1);
+
+        let cached = parallel_perft(&board, 4, 4, Some(&table));
+        let uncached = parallel_perft(&board, 4, 1, None);
+
+        assert_eq!(cached.len(), 48);
+        assert_eq!(cached, uncached);
+        assert_eq!(cached.iter().map(|(_, nodes)| nodes).sum::<u64>(), 4085603);
+
+        // a second run is answered from the table
+        let mut board = board;
+        assert_eq!(cached_perft(&mut board, 4, Some(&table)), 4085603);
+    }
+}
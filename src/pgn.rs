@@ -0,0 +1,555 @@
+//! reading games from pgn files. games are read one at a time as they are iterated over, so a
+//! collection of any size can be worked through without holding it all in memory
+use crate::board::{Board, PositionProblem, ValidationMode};
+use crate::constants::WHITE;
+use crate::conversion::FenError;
+use crate::moves::{Move, MoveParseError};
+use crate::san;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// the tags every exported game has, in the order they are written
+const SEVEN_TAG_ROSTER: [&str; 7] = ["Event", "Site", "Date", "Round", "White", "Black", "Result"];
+
+/// movetext lines are kept under 80 characters
+const MAX_LINE_LENGTH: usize = 79;
+
+/// one game from a pgn file, with its moves already played out on a board
+#[derive(Clone)]
+pub struct PgnGame {
+    /// the tag pairs in the order they were written
+    pub tags: Vec<(String, String)>,
+    /// the position the moves start from, the normal start unless there is a FEN tag
+    pub start_fen: String,
+    pub moves: Vec<Move>,
+    /// the text of each {} or ; comment in the main line, with how many moves had been played
+    /// when it was written
+    pub comments: Vec<(usize, String)>,
+    /// the result written after the moves, or * if there wasn't one
+    pub result: String,
+    /// the position after the last move
+    pub board: Board,
+}
+
+impl PgnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        return self
+            .tags
+            .iter()
+            .find(|(tag_name, _)| tag_name == name)
+            .map(|(_, value)| value.as_str());
+    }
+}
+
+#[derive(Debug)]
+pub enum PgnError {
+    Io(io::Error),
+    /// a line starting with [ that isn't a tag pair
+    BadTag(String),
+    BadFen(FenError),
+    /// a FEN tag that reads but describes a position that can't be played from
+    BadPosition(Vec<PositionProblem>),
+    /// the move at this ply, counted from the start of the game, couldn't be played
+    BadMove {
+        ply: usize,
+        error: MoveParseError,
+    },
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PgnError::Io(error) => write!(f, "could not read pgn: {}", error),
+            PgnError::BadTag(line) => write!(f, "bad tag pair: {}", line),
+            PgnError::BadFen(error) => write!(f, "bad FEN tag: {}", error),
+            PgnError::BadPosition(problems) => write!(f, "impossible FEN tag: {:?}", problems),
+            PgnError::BadMove { ply, error } => write!(f, "at ply {}: {}", ply, error),
+        }
+    }
+}
+
+/// iterates over the games in a pgn source. a game that can't be read comes out as an error
+/// and reading carries on with the next one. only the main line is kept: variations, and any
+/// comments inside them, are skipped
+pub struct PgnReader<R: BufRead> {
+    reader: R,
+    /// a tag line read while finishing the game before it, which starts the next game
+    pending_line: Option<String>,
+    finished: bool,
+}
+
+impl PgnReader<BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        return Ok(PgnReader::new(BufReader::new(File::open(path)?)));
+    }
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> Self {
+        return PgnReader {
+            reader,
+            pending_line: None,
+            finished: false,
+        };
+    }
+
+    fn next_line(&mut self) -> io::Result<Option<String>> {
+        if let Some(line) = self.pending_line.take() {
+            return Ok(Some(line));
+        }
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        return Ok(Some(line));
+    }
+
+    /// the tag lines and movetext of the next game. the movetext ends at its result, or failing
+    /// that at the next tag line outside a comment
+    fn read_game_text(&mut self) -> io::Result<Option<(Vec<String>, String)>> {
+        let mut tag_lines = Vec::new();
+        let mut movetext = String::new();
+        let mut in_comment = false;
+
+        while let Some(line) = self.next_line()? {
+            let trimmed = line.trim();
+            // lines starting with % are escaped and ignored
+            if trimmed.is_empty() || (!in_comment && trimmed.starts_with('%')) {
+                continue;
+            }
+            if !in_comment && trimmed.starts_with('[') {
+                if !movetext.is_empty() {
+                    self.pending_line = Some(line);
+                    break;
+                }
+                tag_lines.push(trimmed.to_string());
+                continue;
+            }
+
+            movetext.push_str(&line);
+            movetext.push('\n');
+
+            let (still_in_comment, last_token) = scan_line(&line, in_comment);
+            in_comment = still_in_comment;
+            if !in_comment && last_token.is_some_and(|token| RESULTS.contains(&token)) {
+                break;
+            }
+        }
+
+        if tag_lines.is_empty() && movetext.is_empty() {
+            return Ok(None);
+        }
+        return Ok(Some((tag_lines, movetext)));
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<PgnGame, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        return match self.read_game_text() {
+            Ok(Some((tag_lines, movetext))) => Some(parse_game(&tag_lines, &movetext)),
+            Ok(None) => {
+                self.finished = true;
+                None
+            }
+            Err(error) => {
+                self.finished = true;
+                Some(Err(PgnError::Io(error)))
+            }
+        };
+    }
+}
+
+/// whether a brace comment is still open at the end of the line, and the line's last token
+/// outside of comments
+fn scan_line(line: &str, mut in_comment: bool) -> (bool, Option<&str>) {
+    let mut last_token = None;
+    let mut token_start = None;
+
+    for (index, character) in line.char_indices() {
+        if in_comment {
+            in_comment = character != '}';
+            continue;
+        }
+        let ends_token = character.is_whitespace() || "{;()".contains(character);
+        if ends_token {
+            if let Some(start) = token_start.take() {
+                last_token = Some(&line[start..index]);
+            }
+            match character {
+                '{' => in_comment = true,
+                ';' => return (false, last_token),
+                ')' => last_token = None,
+                _ => {}
+            }
+        } else if token_start.is_none() {
+            token_start = Some(index);
+        }
+    }
+    if let Some(start) = token_start {
+        last_token = Some(&line[start..]);
+    }
+    return (in_comment, last_token);
+}
+
+/// [Name "value"], with \" and \\ escapes in the value
+fn parse_tag(line: &str) -> Option<(String, String)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?.trim();
+    let (name, value) = inner.split_once(char::is_whitespace)?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+
+    let mut unescaped = String::new();
+    let mut characters = value.chars();
+    while let Some(character) = characters.next() {
+        if character == '\\' {
+            unescaped.extend(characters.next());
+        } else {
+            unescaped.push(character);
+        }
+    }
+    return Some((name.to_string(), unescaped));
+}
+
+fn parse_game(tag_lines: &[String], movetext: &str) -> Result<PgnGame, PgnError> {
+    let mut tags = Vec::new();
+    for line in tag_lines {
+        tags.push(parse_tag(line).ok_or_else(|| PgnError::BadTag(line.clone()))?);
+    }
+
+    let chess960 = tags
+        .iter()
+        .any(|(name, value)| name == "Variant" && value.to_lowercase().contains("960"));
+    let mut board = match tags.iter().find(|(name, _)| name == "FEN") {
+        Some((_, fen)) => {
+            let mut board = Board::from_fen(fen).map_err(PgnError::BadFen)?;
+            board.chess960 = chess960;
+            board
+                .validate(ValidationMode::Lenient)
+                .map_err(PgnError::BadPosition)?;
+            board
+        }
+        None => Board::init(),
+    };
+    board.chess960 = chess960;
+
+    let mut game = PgnGame {
+        tags,
+        start_fen: board.get_fen(),
+        moves: Vec::new(),
+        comments: Vec::new(),
+        result: "*".to_string(),
+        board,
+    };
+
+    let mut characters = movetext.char_indices().peekable();
+    let mut variation_depth = 0;
+    while let Some((start, character)) = characters.next() {
+        match character {
+            '{' | ';' => {
+                let closing = if character == '{' { '}' } else { '\n' };
+                let mut comment = String::new();
+                for (_, inner) in characters.by_ref() {
+                    if inner == closing {
+                        break;
+                    }
+                    comment.push(inner);
+                }
+                if variation_depth == 0 {
+                    game.comments
+                        .push((game.moves.len(), comment.trim().to_string()));
+                }
+            }
+            // variations are skipped, however deeply they nest
+            '(' => variation_depth += 1,
+            ')' => variation_depth = std::cmp::max(variation_depth - 1, 0),
+            _ if character.is_whitespace() => {}
+            _ => {
+                let mut end = movetext.len();
+                while let Some(&(index, next)) = characters.peek() {
+                    if next.is_whitespace() || "{}();".contains(next) {
+                        end = index;
+                        break;
+                    }
+                    characters.next();
+                }
+                if variation_depth == 0 {
+                    play_token(&mut game, &movetext[start..end])?;
+                }
+            }
+        }
+    }
+
+    return Ok(game);
+}
+
+/// move numbers, nags and annotation marks are skipped, results are kept and anything else
+/// has to be a san move
+fn play_token(game: &mut PgnGame, token: &str) -> Result<(), PgnError> {
+    if RESULTS.contains(&token) {
+        game.result = token.to_string();
+        return Ok(());
+    }
+    if token.starts_with('$') || token.chars().all(|c| c == '!' || c == '?') {
+        return Ok(());
+    }
+
+    // 12. and 12... can be written right up against the move
+    let token = if token.starts_with(|c: char| c.is_ascii_digit()) && token.contains('.') {
+        token.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.')
+    } else {
+        token
+    };
+    if token.is_empty() {
+        return Ok(());
+    }
+
+    let chess_move = san::san_to_move(&game.board, token).map_err(|error| PgnError::BadMove {
+        ply: game.moves.len() + 1,
+        error,
+    })?;
+    game.board.make_move(chess_move);
+    game.moves.push(chess_move);
+    return Ok(());
+}
+
+/// what the search made of a move, written after it as {+0.35/12}
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SearchComment {
+    /// centipawns, for the side that made the move
+    pub score: i32,
+    pub depth: i8,
+}
+
+/// pgn for the game played on the board, from the position it started in. the seven standard
+/// tags come first, taken from `tags` where given and ? otherwise, then SetUp and FEN if the
+/// game didn't start from the normal position, then any other tags. `comments` has an entry
+/// for each move that the search had something to say about
+pub fn write_pgn(
+    board: &Board,
+    tags: &[(String, String)],
+    comments: &[Option<SearchComment>],
+) -> String {
+    let given_tag = |name: &str| {
+        tags.iter()
+            .find(|(tag_name, _)| tag_name == name)
+            .map(|(_, value)| value.clone())
+    };
+    let result = given_tag("Result").unwrap_or_else(|| {
+        board
+            .outcome()
+            .map_or("*", |outcome| outcome.result())
+            .to_string()
+    });
+
+    let mut start = board.game_start();
+    let start_fen = start.get_fen();
+
+    let mut pgn = String::new();
+    for name in SEVEN_TAG_ROSTER {
+        let value = match name {
+            "Result" => result.clone(),
+            "Date" => given_tag(name).unwrap_or("????.??.??".to_string()),
+            _ => given_tag(name).unwrap_or("?".to_string()),
+        };
+        pgn.push_str(&format_tag(name, &value));
+    }
+    if start_fen != Board::init().get_fen() {
+        pgn.push_str(&format_tag("SetUp", "1"));
+        pgn.push_str(&format_tag("FEN", &start_fen));
+    }
+    if board.chess960 && given_tag("Variant").is_none() {
+        pgn.push_str(&format_tag("Variant", "Chess960"));
+    }
+    for (name, value) in tags {
+        if !SEVEN_TAG_ROSTER.contains(&name.as_str()) && name != "SetUp" && name != "FEN" {
+            pgn.push_str(&format_tag(name, value));
+        }
+    }
+    pgn.push('\n');
+
+    let mut tokens = Vec::new();
+    // null moves are only ever made inside the search, and have no san
+    let played_moves = board
+        .move_list
+        .iter()
+        .take_while(|chess_move| !chess_move.is_null());
+    for (index, chess_move) in played_moves.enumerate() {
+        let fullmove = start.ply / 2 + 1;
+        if start.side_to_move == WHITE {
+            tokens.push(format!("{}.", fullmove));
+        } else if index == 0 {
+            tokens.push(format!("{}...", fullmove));
+        }
+        tokens.push(san::move_to_san(&start, *chess_move));
+        if let Some(Some(comment)) = comments.get(index) {
+            tokens.push(format!(
+                "{{{:+.2}/{}}}",
+                comment.score as f64 / 100.0,
+                comment.depth
+            ));
+        }
+        start.make_move(*chess_move);
+    }
+    tokens.push(result);
+
+    let mut line_length = 0;
+    for token in tokens {
+        if line_length > 0 && line_length + 1 + token.len() > MAX_LINE_LENGTH {
+            pgn.push('\n');
+            line_length = 0;
+        } else if line_length > 0 {
+            pgn.push(' ');
+            line_length += 1;
+        }
+        line_length += token.len();
+        pgn.push_str(&token);
+    }
+    pgn.push('\n');
+
+    return pgn;
+}
+
+fn format_tag(name: &str, value: &str) -> String {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    return format!("[{} \"{}\"]\n", name, escaped);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::conversion;
+    use crate::pgn::*;
+
+    const GAMES: &str = r#"[Event "Casual \"blitz\""]
+[White "A"]
+[Black "B"]
+[Result "1-0"]
+
+1. e4 e5 2. Nf3 {a comment
+that runs over two lines (with brackets)} Nc6 $1 3. Bb5 (3. Bc4 Bc5 (3... Nf6)) a6?!
+; a rest of line comment 4. d4
+4. Ba4 Nf6 5. O-O 1-0
+
+[Event "Broken"]
+
+1. e4 e5 2. Ke3 *
+
+[Event "From a position"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"]
+
+1.e4 Kd7 2.e5 1/2-1/2
+"#;
+
+    #[test]
+    fn games_from_impossible_positions_are_skipped() {
+        let games = "[FEN \"P3k3/8/8/8/8/8/8/4K3 w - - 0 1\"]\n\n1. Kd2 Kd7 *\n\n1. e4 e5 *\n";
+        let games: Vec<Result<PgnGame, PgnError>> = PgnReader::new(games.as_bytes()).collect();
+
+        assert_eq!(games.len(), 2);
+        assert!(matches!(
+            &games[0],
+            Err(PgnError::BadPosition(problems))
+                if problems == &[PositionProblem::PawnOnBackRank { location: (0, 0) }]
+        ));
+        assert_eq!(games[1].as_ref().unwrap().moves.len(), 2);
+    }
+
+    #[test]
+    fn keeps_main_line_comments_and_skips_variations() {
+        let game =
+            "{opening} 1. e4 {best by test} e5 (1... c5 {sicilian} 2. Nf3) 2. Nf3 ; develops\n\
+                    2... Nc6 *\n";
+        let games: Vec<Result<PgnGame, PgnError>> = PgnReader::new(game.as_bytes()).collect();
+        let game = games[0].as_ref().unwrap();
+
+        assert_eq!(game.moves.len(), 4);
+        assert_eq!(
+            game.comments,
+            vec![
+                (0, "opening".to_string()),
+                (1, "best by test".to_string()),
+                (3, "develops".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_games_one_after_another() {
+        let games: Vec<Result<PgnGame, PgnError>> = PgnReader::new(GAMES.as_bytes()).collect();
+        assert_eq!(games.len(), 3);
+
+        let first = games[0].as_ref().unwrap();
+        assert_eq!(first.tag("Event"), Some("Casual \"blitz\""));
+        assert_eq!(first.result, "1-0");
+        let notations: Vec<String> = first
+            .moves
+            .iter()
+            .map(conversion::convert_move_to_notation)
+            .collect();
+        assert_eq!(
+            notations.join(" "),
+            "e2e4 e7e5 g1f3 b8c6 f1b5 a7a6 b5a4 g8f6 e1g1"
+        );
+
+        // the broken game is reported and the one after it still reads
+        assert!(matches!(
+            games[1],
+            Err(PgnError::BadMove {
+                ply: 3,
+                error: MoveParseError::Illegal(_)
+            })
+        ));
+
+        let third = games[2].as_ref().unwrap();
+        assert_eq!(third.start_fen, "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+        assert_eq!(third.result, "1/2-1/2");
+        assert_eq!(third.board.get_fen(), "8/3k4/8/4P3/8/8/8/4K3 b - - 0 2");
+    }
+
+    #[test]
+    fn games_without_tags_end_at_their_result() {
+        let games: Vec<Result<PgnGame, PgnError>> =
+            PgnReader::new("1. d4 d5 0-1\n1. c4 *\n".as_bytes()).collect();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].as_ref().unwrap().moves.len(), 2);
+        assert_eq!(games[1].as_ref().unwrap().moves.len(), 1);
+    }
+
+    #[test]
+    fn written_games_read_back() {
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/4P3/R3K3 b Q - 0 12").unwrap();
+        for notation in ["e8d7", "e2e4", "d7c6", "e1c1"] {
+            board.make_move_with_notation(notation.to_string()).unwrap();
+        }
+        let tags = vec![
+            ("White".to_string(), "ChooChoo".to_string()),
+            ("Annotator".to_string(), "a \"quoted\" name".to_string()),
+        ];
+        let comments = [
+            None,
+            Some(SearchComment {
+                score: 35,
+                depth: 7,
+            }),
+        ];
+
+        let pgn = write_pgn(&board, &tags, &comments);
+        assert!(pgn.starts_with("[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n"));
+        assert!(pgn.contains("[SetUp \"1\"]\n[FEN \"4k3/8/8/8/8/8/4P3/R3K3 b Q - 0 12\"]\n"));
+        assert!(pgn.ends_with("\n\n12... Kd7 13. e4 {+0.35/7} Kc6 14. O-O-O *\n"));
+
+        let game = PgnReader::new(pgn.as_bytes()).next().unwrap().unwrap();
+        assert_eq!(game.tag("Annotator"), Some("a \"quoted\" name"));
+        assert_eq!(game.tag("White"), Some("ChooChoo"));
+        assert_eq!(game.moves, board.move_list);
+        assert_eq!(game.board.get_fen(), board.get_fen());
+    }
+}
@@ -0,0 +1,289 @@
+//! standard algebraic notation, the way moves are written for people: Nbd7, exd6, e8=Q+, O-O.
+//! reading it is lenient about the usual variations, and every move is matched against the
+//! legal moves of the position so a parsed move can always be made
+use crate::board::Board;
+use crate::constants::*;
+use crate::conversion;
+use crate::evaluate;
+use crate::movegen;
+use crate::moves::*;
+
+/// the san for a legal move in the position on the board, before it is made
+pub fn move_to_san(board: &Board, chess_move: Move) -> String {
+    let mut san = String::new();
+
+    if chess_move.is_castle() {
+        san.push_str(if chess_move.flag() == KING_CASTLE {
+            "O-O"
+        } else {
+            "O-O-O"
+        });
+    } else {
+        let piece = board.get_piece(chess_move.from());
+        let to = chess_move.to();
+        let to_square = BOARD_COORDINATES[to.0][to.1];
+
+        if piece == PAWN {
+            if chess_move.is_capture() {
+                san.push(file_letter(chess_move.from().1));
+                san.push('x');
+            }
+            san.push_str(to_square);
+            if let Some(promotion) = chess_move.promotion_to() {
+                san.push('=');
+                san.push(conversion::convert_piece_to_alphabetic(promotion).to_ascii_uppercase());
+            }
+        } else {
+            san.push(conversion::convert_piece_to_alphabetic(piece).to_ascii_uppercase());
+            san.push_str(&disambiguation(board, chess_move, piece));
+            if chess_move.is_capture() {
+                san.push('x');
+            }
+            san.push_str(to_square);
+        }
+    }
+
+    let mut board_after = board.clone();
+    board_after.make_move(chess_move);
+    if evaluate::is_in_check(&board_after, board_after.side_to_move, None) {
+        if movegen::generate_legal_moves(&board_after).is_empty() {
+            san.push('#');
+        } else {
+            san.push('+');
+        }
+    }
+
+    return san;
+}
+
+/// the least needed to tell the move apart from other moves of the same kind of piece to the
+/// same square: the file if that is enough, then the rank, then both
+fn disambiguation(board: &Board, chess_move: Move, piece: i8) -> String {
+    let from = chess_move.from();
+    let others: Vec<(usize, usize)> = movegen::generate_legal_moves(board)
+        .iter()
+        .filter(|other| {
+            !other.is_castle()
+                && other.to() == chess_move.to()
+                && other.from() != from
+                && board.get_piece(other.from()) == piece
+        })
+        .map(|other| other.from())
+        .collect();
+
+    if others.is_empty() {
+        return String::new();
+    }
+    if others.iter().all(|other| other.1 != from.1) {
+        return file_letter(from.1).to_string();
+    }
+    if others.iter().all(|other| other.0 != from.0) {
+        return rank_digit(from.0).to_string();
+    }
+    return format!("{}{}", file_letter(from.1), rank_digit(from.0));
+}
+
+/// the legal move the san describes. check and annotation marks, "e.p.", "=" before a
+/// promotion, "x" and "-" are all optional, and castling can be written with zeros
+pub fn san_to_move(board: &Board, san: &str) -> Result<Move, MoveParseError> {
+    let mut text: String = san.trim().to_string();
+    if let Some(stripped) = text.strip_suffix("e.p.") {
+        text = stripped.trim_end().to_string();
+    }
+    let text = text.trim_end_matches(['+', '#', '!', '?']);
+
+    let legal_moves = movegen::generate_legal_moves(board);
+
+    let castle_flag = match text.replace(['0', 'o'], "O").as_str() {
+        "O-O" => Some(KING_CASTLE),
+        "O-O-O" => Some(QUEEN_CASTLE),
+        _ => None,
+    };
+    if let Some(castle_flag) = castle_flag {
+        return legal_moves
+            .iter()
+            .find(|chess_move| chess_move.flag() == castle_flag)
+            .ok_or_else(|| MoveParseError::Illegal(san.to_string()));
+    }
+
+    let mut characters: Vec<char> = text.chars().filter(|&c| c != 'x' && c != '-').collect();
+
+    let piece = match characters.first() {
+        Some('K') => KING,
+        Some('Q') => QUEEN,
+        Some('R') => ROOK,
+        Some('B') => BISHOP,
+        Some('N') => KNIGHT,
+        Some('a'..='h') => PAWN,
+        _ => return Err(MoveParseError::Malformed(san.to_string())),
+    };
+    if piece != PAWN {
+        characters.remove(0);
+    }
+
+    // a promotion piece comes straight after the destination rank, with or without the =
+    let mut promotion = None;
+    if characters.len() >= 2 && characters[characters.len() - 2] == '=' {
+        characters.remove(characters.len() - 2);
+    }
+    if characters.len() >= 3 && characters[characters.len() - 2].is_ascii_digit() {
+        promotion = match characters.pop().map(|c| c.to_ascii_uppercase()) {
+            Some('Q') => Some(QUEEN),
+            Some('R') => Some(ROOK),
+            Some('B') => Some(BISHOP),
+            Some('N') => Some(KNIGHT),
+            _ => return Err(MoveParseError::Malformed(san.to_string())),
+        };
+    }
+
+    if characters.len() < 2 || characters.len() > 4 {
+        return Err(MoveParseError::Malformed(san.to_string()));
+    }
+    let (from_hint, target) = characters.split_at(characters.len() - 2);
+    let to = match target {
+        [file @ 'a'..='h', rank @ '1'..='8'] => location_from_file_and_rank(*file, *rank),
+        _ => return Err(MoveParseError::Malformed(san.to_string())),
+    };
+
+    let mut from_file = None;
+    let mut from_rank = None;
+    for hint in from_hint {
+        match hint {
+            'a'..='h' if from_file.is_none() => from_file = Some((*hint as u8 - b'a') as usize),
+            '1'..='8' if from_rank.is_none() => {
+                from_rank = Some(8 - hint.to_digit(10).unwrap() as usize)
+            }
+            _ => return Err(MoveParseError::Malformed(san.to_string())),
+        }
+    }
+
+    // a promotion written without its piece is taken to be a queen
+    if piece == PAWN && promotion.is_none() && (to.0 == 0 || to.0 == 7) {
+        promotion = Some(QUEEN);
+    }
+
+    let matches: Vec<Move> = legal_moves
+        .iter()
+        .filter(|chess_move| {
+            !chess_move.is_castle()
+                && chess_move.to() == to
+                && board.get_piece(chess_move.from()) == piece
+                && from_file.is_none_or(|file| chess_move.from().1 == file)
+                && from_rank.is_none_or(|rank| chess_move.from().0 == rank)
+                && chess_move.promotion_to() == promotion
+        })
+        .collect();
+
+    return match matches.len() {
+        0 => Err(MoveParseError::Illegal(san.to_string())),
+        1 => Ok(matches[0]),
+        _ => Err(MoveParseError::Ambiguous(san.to_string())),
+    };
+}
+
+/// the moves played on the board so far, in san, replayed from where the game started. null
+/// moves are only ever made inside the search, and have no san
+pub fn game_to_san(board: &Board) -> Vec<String> {
+    let mut replay = board.game_start();
+
+    let mut sans = Vec::new();
+    for chess_move in board
+        .move_list
+        .iter()
+        .take_while(|chess_move| !chess_move.is_null())
+    {
+        sans.push(move_to_san(&replay, *chess_move));
+        replay.make_move(*chess_move);
+    }
+    return sans;
+}
+
+fn file_letter(column: usize) -> char {
+    return (b'a' + column as u8) as char;
+}
+fn rank_digit(row: usize) -> char {
+    return (b'8' - row as u8) as char;
+}
+fn location_from_file_and_rank(file: char, rank: char) -> (usize, usize) {
+    return (
+        8 - rank.to_digit(10).unwrap() as usize,
+        (file as u8 - b'a') as usize,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::conversion;
+    use crate::movegen;
+    use crate::san::*;
+
+    fn san_for(fen: &str, notation: &str) -> String {
+        let board = conversion::convert_fen_to_board(fen);
+        let chess_move = board
+            .convert_notation_to_move(notation.to_string())
+            .unwrap();
+        return move_to_san(&board, chess_move);
+    }
+
+    #[test]
+    fn writes_minimal_disambiguation_and_suffixes() {
+        // knights on b8 and f6 can both reach d7
+        let fen = "rnbqkb1r/ppp1pppp/5n2/3p4/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1";
+        assert_eq!(san_for(fen, "b8d7"), "Nbd7");
+
+        // rooks on the same file need the rank
+        assert_eq!(san_for("4k3/8/R7/8/8/8/R7/4K3 w - - 0 1", "a2a4"), "R2a4");
+        assert_eq!(san_for("6k1/5ppp/8/8/8/8/8/K2R4 w - - 0 1", "d1d8"), "Rd8#");
+        assert_eq!(san_for("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "a1a8"), "Ra8+");
+        assert_eq!(
+            san_for("3nk3/4P3/8/8/8/8/8/4K3 w - - 0 1", "e7d8q"),
+            "exd8=Q+"
+        );
+        assert_eq!(san_for("3k4/8/8/8/8/8/8/R3K2R w KQ - 0 1", "e1g1"), "O-O");
+        assert_eq!(
+            san_for("3k4/8/8/8/8/8/8/R3K2R w KQ - 0 1", "e1c1"),
+            "O-O-O+"
+        );
+    }
+
+    #[test]
+    fn reads_san_leniently() {
+        let board =
+            conversion::convert_fen_to_board("2b3k1/3P4/5n2/2pP2N1/8/2N2Q2/3BB3/R3K2R w KQ c6 0 1");
+        let uci_for =
+            |san: &str| san_to_move(&board, san).map(|m| conversion::convert_move_to_notation(&m));
+
+        assert_eq!(uci_for("dxc6 e.p."), Ok("d5c6".to_string()));
+        assert_eq!(uci_for("dxc6"), Ok("d5c6".to_string()));
+        assert_eq!(uci_for("0-0"), Ok("e1g1".to_string()));
+        assert_eq!(uci_for("O-O-O"), Ok("e1c1".to_string()));
+        assert_eq!(uci_for("Qxf6!?"), Ok("f3f6".to_string()));
+        assert_eq!(uci_for("dxc8=Q+"), Ok("d7c8q".to_string()));
+        assert_eq!(uci_for("dxc8N"), Ok("d7c8n".to_string()));
+        assert_eq!(uci_for("d8"), Ok("d7d8q".to_string()));
+        assert_eq!(
+            uci_for("Ne4"),
+            Err(MoveParseError::Ambiguous("Ne4".to_string()))
+        );
+        assert_eq!(uci_for("Nce4"), Ok("c3e4".to_string()));
+        assert_eq!(
+            uci_for("Ke3"),
+            Err(MoveParseError::Illegal("Ke3".to_string()))
+        );
+        assert_eq!(
+            uci_for("Zz9"),
+            Err(MoveParseError::Malformed("Zz9".to_string()))
+        );
+    }
+
+    #[test]
+    fn san_round_trips_for_every_legal_move() {
+        for fen in crate::constants::BENCH_FENS.iter().take(20) {
+            let board = conversion::convert_fen_to_board(fen);
+            for chess_move in movegen::generate_legal_moves(&board).iter() {
+                let san = move_to_san(&board, chess_move);
+                assert_eq!(san_to_move(&board, &san), Ok(chess_move), "{} {}", fen, san);
+            }
+        }
+    }
+}
@@ -1,16 +1,38 @@
+use crate::bitboard::colour_index;
 use crate::board::Board;
 use crate::constants::*;
 use crate::conversion;
 use crate::evaluate;
 use crate::evaluate::evaluate;
 use crate::movegen;
+use crate::movepick::*;
 use crate::moves::*;
+use crate::see::see;
 use std::time::Instant;
 
+/// how far from the root the search keeps killer moves for
+pub const MAX_PLY: usize = 128;
+/// number of entries in the transposition table, a power of two so the key can be masked
+const TRANSPOSITION_TABLE_SIZE: usize = 1 << 18;
+/// history scores are halved once one gets this big, so old cutoffs fade
+const HISTORY_MAX: i32 = 1 << 20;
+
+/// how the stored score relates to the real score of the position
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bound {
+    Exact,
+    /// the search cut off, the real score is at least this
+    Lower,
+    /// nothing raised alpha, the real score is at most this
+    Upper,
+}
+#[derive(Clone, Copy)]
 pub struct TranspositionTableEntry {
     pub position_hash: u64,
     pub depth_distance: i8,
     pub position_terminal_score: i32,
+    pub bound: Bound,
+    pub best_move: Move,
 }
 pub struct MoveNode {
     pub move_notation: String,
@@ -34,18 +56,52 @@ pub struct SearchEngine {
     pub use_time_management: bool,
     pub searching_side: i8,
     pub move_overhead: u128,
-    pub transposition_table: Vec<TranspositionTableEntry>,
+    pub transposition_table: Vec<Option<TranspositionTableEntry>>,
+    /// two quiet moves per ply that caused a cutoff, tried straight after the good captures
+    pub killer_moves: [[Move; 2]; MAX_PLY],
+    /// the quiet move that last refuted each opponent move, indexed by its from and to square
+    pub counter_moves: [[Move; 64]; 64],
+    pub history: HistoryTable,
+    root_ply: i32,
 }
 
-pub fn order_moves(moves: &mut Vec<Move>) {
-    for i in 0..moves.len() {
-        let move_to_score = moves.get_mut(i).unwrap();
-        let value = MVV_LVA[move_to_score.to_piece as usize][move_to_score.from_piece as usize];
+/// a root move with the score from the last finished iteration, kept apart from the move itself
+struct RootMove {
+    chess_move: Move,
+    sort_score: i32,
+    search_score: i32,
+    illegal_move: bool,
+}
 
-        move_to_score.sort_score += value;
+pub fn order_moves(board: &Board, moves: &mut MoveList) {
+    for scored_move in moves.scored_moves_mut() {
+        let chess_move = scored_move.chess_move;
+        let to_piece = if chess_move.is_en_passant() {
+            PAWN
+        } else {
+            board.get_piece(chess_move.to())
+        };
+        let from_piece = board.get_piece(chess_move.from());
+
+        // a capture that loses material goes behind the quiet moves
+        let exchange = if chess_move.is_capture() {
+            see(board, chess_move)
+        } else {
+            0
+        };
+        if exchange < 0 {
+            scored_move.score += exchange;
+        } else {
+            scored_move.score += MVV_LVA[to_piece as usize][from_piece as usize] as i32;
+        }
     }
 
-    moves.sort_by(|a, b| b.sort_score.cmp(&a.sort_score));
+    moves.sort_by_score();
+}
+impl Default for SearchEngine {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 impl SearchEngine {
     pub fn new() -> Self {
@@ -63,35 +119,66 @@ impl SearchEngine {
             btime: 0,
             use_time_management: false,
             searching_side: WHITE,
-            transposition_table: Vec::new(),
+            transposition_table: vec![None; TRANSPOSITION_TABLE_SIZE],
+            killer_moves: [[Move::NULL; 2]; MAX_PLY],
+            counter_moves: [[Move::NULL; 64]; 64],
+            history: [[[0; 64]; 64]; 2],
+            root_ply: 0,
         }
     }
     fn clear_tt(&mut self) {
-        self.transposition_table = Vec::new();
+        self.transposition_table.fill(None);
     }
-    fn delete_tt_pos_for_hash(&mut self, position_hash: u64) {
-        self.transposition_table
-            .retain(|entry| entry.position_hash != position_hash);
+    fn tt_index(position_hash: u64) -> usize {
+        return position_hash as usize & (TRANSPOSITION_TABLE_SIZE - 1);
     }
+    /// always replaces whatever was in the slot
     fn add_position_to_tt(
         &mut self,
         position_hash: u64,
         position_terminal_score: i32,
         depth_distance: i8,
+        bound: Bound,
+        best_move: Move,
     ) {
-        self.transposition_table.push(TranspositionTableEntry {
+        self.transposition_table[Self::tt_index(position_hash)] = Some(TranspositionTableEntry {
             position_hash,
             position_terminal_score,
-            depth_distance: depth_distance,
+            depth_distance,
+            bound,
+            best_move,
         });
     }
     fn get_position_from_tt(&self, position_hash: u64) -> Option<&TranspositionTableEntry> {
-        for entry in self.transposition_table.iter() {
-            if entry.position_hash == position_hash {
-                return Some(entry);
+        return self.transposition_table[Self::tt_index(position_hash)]
+            .as_ref()
+            .filter(|entry| entry.position_hash == position_hash);
+    }
+    fn ply_from_root(&self, board: &Board) -> usize {
+        return ((board.ply - self.root_ply).max(0) as usize).min(MAX_PLY - 1);
+    }
+    /// remember a quiet move that caused a cutoff, so it is tried early in similar positions
+    fn update_quiet_move_tables(&mut self, board: &Board, chess_move: Move, depth: i8) {
+        let ply = self.ply_from_root(board);
+        if self.killer_moves[ply][0] != chess_move {
+            self.killer_moves[ply][1] = self.killer_moves[ply][0];
+            self.killer_moves[ply][0] = chess_move;
+        }
+
+        if let Some(previous_move) = board.move_list.last() {
+            self.counter_moves[previous_move.from_square()][previous_move.to_square()] = chess_move;
+        }
+
+        let side = colour_index(board.side_to_move);
+        let entry = &mut self.history[side][chess_move.from_square()][chess_move.to_square()];
+        *entry += depth as i32 * depth as i32;
+        if *entry > HISTORY_MAX {
+            for from_square in self.history[side].iter_mut() {
+                for score in from_square.iter_mut() {
+                    *score /= 2;
+                }
             }
         }
-        return None;
     }
     pub fn get_allowed_time(&self, side: i8) -> u128 {
         if self.use_time_management {
@@ -107,7 +194,7 @@ impl SearchEngine {
 
             let increment = if side == WHITE { self.winc } else { self.binc };
             // println!("{} {}", self.move_overhead, increment);
-            return (time_left / 30 + increment - 2 * self.move_overhead) as u128;
+            return time_left / 30 + increment - 2 * self.move_overhead;
         } else {
             return 10000;
         }
@@ -124,14 +211,13 @@ impl SearchEngine {
         if depth == 0 {
             self.nodes += 1;
 
-            return evaluate::evaluate(&board);
+            return evaluate::evaluate(board);
         };
 
         // generate moves for current depth of board
-        let mut moves_for_current_depth =
-            movegen::generate_pseudo_legal_moves(board, board.side_to_move, false);
+        let mut moves_for_current_depth = movegen::generate_legal_moves(board);
 
-        order_moves(&mut moves_for_current_depth);
+        order_moves(board, &mut moves_for_current_depth);
 
         if maximizing_player {
             let mut max_eval = i32::MIN;
@@ -167,14 +253,11 @@ impl SearchEngine {
         }
     }
     pub fn quiescence_search(&mut self, board: &mut Board, mut alpha: i32, beta: i32) -> i32 {
-        // if in check, return.
-        // if evaluate::is_in_check(board, board.side_to_move, None) {
-        //     return evaluate(board);
-        // }
-
-        // if evaluate::is_in_check(board, board.side_to_move * -1, None) {
-        //     return evaluate(board);
-        // }
+        // in check there is no standing pat, every way out has to be looked at
+        if evaluate::is_in_check(board, board.side_to_move, None) {
+            return self.quiescence_evasions(board, alpha, beta);
+        }
+
         // searches the captures available
         let stand_pat = evaluate(board);
         if stand_pat >= beta {
@@ -184,20 +267,9 @@ impl SearchEngine {
             alpha = stand_pat;
         }
 
-        // generate moves for current depth of board
-        let mut moves_for_current_depth =
-            movegen::generate_pseudo_legal_moves(board, board.side_to_move, false);
-
-        order_moves(&mut moves_for_current_depth);
-
-        for generated_move in moves_for_current_depth.iter() {
-            if generated_move.to_piece == EMPTY {
-                continue;
-            }
-            if generated_move.to_piece == KING {
-                return alpha;
-            }
+        let mut move_picker = MovePicker::new_for_quiescence();
 
+        while let Some(generated_move) = move_picker.next_move(board, &self.history) {
             board.make_move(generated_move);
             let score = -self.quiescence_search(board, -beta, -alpha);
             board.un_make_move(generated_move);
@@ -213,43 +285,111 @@ impl SearchEngine {
 
         return alpha;
     }
+    fn quiescence_evasions(&mut self, board: &mut Board, mut alpha: i32, beta: i32) -> i32 {
+        let evasions = movegen::generate_evasions(board);
+        if evasions.is_empty() {
+            return -CHECKMATE_SCORE;
+        }
+
+        let mut best_value = i32::MIN;
+        for generated_move in evasions.iter() {
+            board.make_move(generated_move);
+            let score = -self.quiescence_search(board, -beta, -alpha);
+            board.un_make_move(generated_move);
+
+            best_value = std::cmp::max(best_value, score);
+            if score >= beta {
+                return score;
+            }
+            alpha = std::cmp::max(alpha, score);
+        }
+
+        return best_value;
+    }
     pub fn alpha_beta(&mut self, board: &mut Board, depth: i8, mut alpha: i32, beta: i32) -> i32 {
         let mut best_value = i32::MIN;
+        // a draw, unless the last move before the clock ran out was checkmate
+        if board.is_fifty_move_draw()
+            && (!evaluate::is_in_check(board, board.side_to_move, None)
+                || !movegen::generate_legal_moves(board).is_empty())
+        {
+            return 0;
+        }
+
         if depth == 0 {
             self.nodes += 1;
             return self.quiescence_search(board, alpha, beta); //
-                                                               // return evaluate::evaluate(&board);
+                                                               // return evaluate::evaluate(board);
         };
 
-        if let Some(entry) = self.get_position_from_tt(conversion::hash_board_state(board)) {
-            return entry.position_terminal_score;
+        let original_alpha = alpha;
+        let mut hash_move = Move::NULL;
+        if let Some(entry) = self.get_position_from_tt(board.zobrist_key) {
+            let score = entry.position_terminal_score;
+            let usable = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => score >= beta,
+                Bound::Upper => score <= alpha,
+            };
+            if entry.depth_distance >= depth && usable {
+                return score;
+            }
+            hash_move = entry.best_move;
         }
 
-        let mut moves_for_current_depth =
-            movegen::generate_pseudo_legal_moves(board, board.side_to_move, false);
-
-        order_moves(&mut moves_for_current_depth);
+        let counter_move = board.move_list.last().map_or(Move::NULL, |previous_move| {
+            self.counter_moves[previous_move.from_square()][previous_move.to_square()]
+        });
+        let mut move_picker = MovePicker::new(
+            hash_move,
+            self.killer_moves[self.ply_from_root(board)],
+            counter_move,
+        );
 
-        for generated_move in moves_for_current_depth.iter() {
+        let mut best_move = Move::NULL;
+        while let Some(generated_move) = move_picker.next_move(board, &self.history) {
             board.make_move(generated_move);
 
             let eval = -self.alpha_beta(board, depth - 1, -beta, -alpha);
 
-            // let position_hash = conversion::hash_board_state(board);
-            // if self.get_position_from_tt(position_hash, depth).is_none() {
-            //     self.delete_tt_pos_for_hash(position_hash);
-            //     self.add_position_to_tt(conversion::hash_board_state(board), eval, depth);
-            // }
-
             board.un_make_move(generated_move);
 
-            best_value = std::cmp::max(best_value, eval);
+            if eval > best_value {
+                best_value = eval;
+                best_move = generated_move;
+            }
             alpha = std::cmp::max(alpha, eval);
 
             if eval >= beta {
+                if !generated_move.is_capture() && !generated_move.is_promotion() {
+                    self.update_quiet_move_tables(board, generated_move, depth);
+                }
+                self.add_position_to_tt(
+                    board.zobrist_key,
+                    best_value,
+                    depth,
+                    Bound::Lower,
+                    best_move,
+                );
                 return best_value;
             }
         }
+
+        // no legal moves is either checkmate or stalemate.
+        // prefer the mates closest to the root, which have the most depth left
+        if best_move.is_null() {
+            if evaluate::is_in_check(board, board.side_to_move, None) {
+                return -CHECKMATE_SCORE - depth as i32;
+            }
+            return 0;
+        }
+
+        let bound = if alpha > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        self.add_position_to_tt(board.zobrist_key, best_value, depth, bound, best_move);
         return best_value;
     }
 
@@ -261,69 +401,56 @@ impl SearchEngine {
         let mut best_moves = Vec::new();
 
         self.clear_tt();
+        self.killer_moves = [[Move::NULL; 2]; MAX_PLY];
+        self.root_ply = board.ply;
         self.searching_side = board.side_to_move;
         self.nodes = 0;
         self.start = Instant::now();
-        let current_side = board.side_to_move;
-
-        let currently_in_check = evaluate::is_in_check(board, current_side, None);
 
         // generate moves for current depth of board
-        let mut moves_for_current_depth =
-            movegen::generate_pseudo_legal_moves(board, board.side_to_move, currently_in_check);
-        order_moves(&mut moves_for_current_depth);
+        let mut generated_moves = movegen::generate_legal_moves(board);
+        order_moves(board, &mut generated_moves);
+        let mut moves_for_current_depth: Vec<RootMove> = generated_moves
+            .scored_moves()
+            .iter()
+            .map(|scored_move| RootMove {
+                chess_move: scored_move.chess_move,
+                sort_score: scored_move.score,
+                search_score: 0,
+                illegal_move: false,
+            })
+            .collect();
 
         while searching {
-            for generated_move in moves_for_current_depth.iter_mut() {
-                if generated_move.illegal_move {
-                    continue;
-                }
-                board.make_move(generated_move);
-
-                // check not moving self into check
-                if evaluate::is_in_check(
-                    board,
-                    current_side,
-                    generated_move.castling_intermediary_square,
-                ) {
-                    generated_move.illegal_move = true;
-                    board.un_make_move(generated_move);
+            for root_move in moves_for_current_depth.iter_mut() {
+                if root_move.illegal_move {
                     continue;
                 }
+                board.make_move(root_move.chess_move);
 
                 if board.has_positions_repeated() {
-                    generated_move.illegal_move = true;
-                    board.un_make_move(generated_move);
+                    root_move.illegal_move = true;
+                    board.un_make_move(root_move.chess_move);
                     continue;
                 }
-                // let position_hash = conversion::hash_board_state_for_tt(board);
-                // match self.get_position_from_tt(position_hash) {
-                //     Some(entry) => {
-                //         generated_move.search_score = entry.position_terminal_score;
-                //     }
-                //     None => {
-
-                //         self.add_position_to_tt(position_hash, generated_move.search_score);
-                //     }
-                // }
-                generated_move.search_score =
+                root_move.search_score =
                     -self.alpha_beta(board, self.current_depth, i32::MIN + 1, i32::MAX);
-                board.un_make_move(generated_move);
+                board.un_make_move(root_move.chess_move);
 
-                if self.use_time_management {
-                    if self.start.elapsed().as_millis() > self.get_allowed_time(self.searching_side)
-                    {
-                        searching = false;
-                        break;
-                    }
+                if self.use_time_management
+                    && self.start.elapsed().as_millis() > self.get_allowed_time(self.searching_side)
+                {
+                    searching = false;
+                    break;
                 }
             }
 
-            if searching && self.use_time_management {
-                if self.start.elapsed().as_millis() > self.get_allowed_time(self.searching_side) {
-                    println!("time limit reached");
-                    searching = false;
-                }
+            if searching
+                && self.use_time_management
+                && self.start.elapsed().as_millis() > self.get_allowed_time(self.searching_side)
+            {
+                println!("time limit reached");
+                searching = false;
             }
 
             if searching && (self.current_depth < self.depth || self.use_time_management) {
@@ -342,25 +469,18 @@ impl SearchEngine {
             });
         }
 
-        for generated_move in moves_for_current_depth.iter() {
-            // println!(
-            //     "{} {} {} {}",
-            //     conversion::convert_move_to_notation(generated_move),
-            //     generated_move.search_score,
-            //     generated_move.sort_score,
-            //     generated_move.illegal_move
-            // );
-            if generated_move.illegal_move {
+        for root_move in moves_for_current_depth.iter() {
+            if root_move.illegal_move {
                 continue;
             }
 
-            if generated_move.search_score > best_score {
-                best_score = generated_move.search_score;
-                best_move = generated_move.clone();
+            if root_move.search_score > best_score {
+                best_score = root_move.search_score;
+                best_move = root_move.chess_move;
             }
             best_moves.push(BestMoves {
-                best_move: generated_move.clone(),
-                best_score: generated_move.search_score,
+                best_move: root_move.chess_move,
+                best_score: root_move.search_score,
             });
         }
 
@@ -374,31 +494,16 @@ impl SearchEngine {
             return 1;
         }
 
-        let current_side = board.side_to_move;
+        let moves_for_current_depth = movegen::generate_legal_moves(board);
 
-        let currently_in_check = evaluate::is_in_check(board, current_side, None);
-
-        let mut moves_for_current_depth =
-            movegen::generate_pseudo_legal_moves(board, board.side_to_move, currently_in_check);
+        // every legal move is one leaf, no need to make them
+        if depth == 1 && !first_call {
+            return moves_for_current_depth.len() as i128;
+        }
 
-        for generated_move in moves_for_current_depth.iter_mut() {
+        for generated_move in moves_for_current_depth.iter() {
             board.make_move(generated_move);
 
-            if board.has_positions_repeated() {
-                generated_move.illegal_move = true;
-                board.un_make_move(generated_move);
-                continue;
-            }
-
-            if evaluate::is_in_check(
-                board,
-                current_side,
-                generated_move.castling_intermediary_square,
-            ) {
-                board.un_make_move(generated_move);
-                continue;
-            }
-
             nodes_per_root_move = self.perft(board, depth - 1, false);
             nodes += nodes_per_root_move;
             board.un_make_move(generated_move);
@@ -406,7 +511,10 @@ impl SearchEngine {
             if first_call {
                 // update root node here with number
                 self.move_nodes.push(MoveNode {
-                    move_notation: conversion::convert_move_to_notation(generated_move),
+                    move_notation: conversion::convert_move_to_uci_notation(
+                        &generated_move,
+                        board.chess960,
+                    ),
                     nodes: nodes_per_root_move,
                 });
                 self.nodes += nodes;
@@ -419,6 +527,7 @@ impl SearchEngine {
 
 #[cfg(test)]
 mod tests {
+    use crate::constants::CHECKMATE_SCORE;
     use crate::conversion;
     use crate::search::Board;
     use crate::search::SearchEngine;
@@ -599,4 +708,36 @@ mod tests {
         let nodes = engine.perft(&mut board, 3, true);
         assert_eq!(nodes, 62379);
     }
+
+    #[test]
+    fn perft_3_chess960_position_1() {
+        let mut engine = SearchEngine::new();
+        let mut board = conversion::convert_fen_to_board(
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        );
+
+        let nodes = engine.perft(&mut board, 3, true);
+        assert_eq!(nodes, 12189);
+    }
+    #[test]
+    fn perft_3_chess960_position_5() {
+        let mut engine = SearchEngine::new();
+        let mut board = conversion::convert_fen_to_board(
+            "1nbbnrkr/p1p1ppp1/3p4/1p3P1p/3Pq2P/8/PPP1P1P1/QNBBNRKR w HFhf - 0 9",
+        );
+
+        let nodes = engine.perft(&mut board, 3, true);
+        assert_eq!(nodes, 31058);
+    }
+
+    #[test]
+    fn fifty_move_rule_scores_a_draw() {
+        let mut engine = SearchEngine::new();
+        let mut board = conversion::convert_fen_to_board("7k/8/8/8/8/8/8/KQ6 b - - 100 80");
+        assert_eq!(engine.alpha_beta(&mut board, 2, i32::MIN + 1, i32::MAX), 0);
+
+        // mate on the hundredth half move still counts
+        let mut board = conversion::convert_fen_to_board("3R2k1/5ppp/8/8/8/8/8/K7 b - - 100 80");
+        assert!(engine.alpha_beta(&mut board, 2, i32::MIN + 1, i32::MAX) <= -CHECKMATE_SCORE);
+    }
 }
@@ -0,0 +1,252 @@
+//! static exchange evaluation: what a capture wins or loses once both sides have finished
+//! recapturing on its square with their least valuable pieces
+use crate::bitboard::*;
+use crate::board::Board;
+use crate::constants::*;
+use crate::evaluate::get_piece_value;
+use crate::movegen::attackers_to;
+use crate::moves::Move;
+
+/// the material the side to move ends up with from the exchange the move starts. pawns that
+/// recapture on the last rank promote to queens, and pins are not looked at
+pub fn see(board: &Board, chess_move: Move) -> i32 {
+    let to_square = chess_move.to_square();
+    let last_rank = RANK_8 | RANK_1;
+    let promotes_on_square = last_rank & (1u64 << to_square) != EMPTY_BITBOARD;
+    let mut occupied = board.occupied();
+
+    // the pawn taken en passant is not on the destination square
+    let captured_piece = if chess_move.is_en_passant() {
+        let captured_square = square_from_location((chess_move.from().0, chess_move.to().1));
+        occupied &= !(1u64 << captured_square);
+        PAWN
+    } else {
+        board.get_piece(chess_move.to())
+    };
+
+    let mut gain = [0i32; 32];
+    let mut depth = 0;
+    // the piece standing on the square, waiting to be taken
+    let mut piece_on_square = board.get_piece(chess_move.from());
+    gain[0] = get_piece_value(captured_piece);
+    if let Some(promotion) = chess_move.promotion_to() {
+        gain[0] += get_piece_value(promotion) - get_piece_value(PAWN);
+        piece_on_square = promotion;
+    }
+
+    let mut attacker_bit = 1u64 << chess_move.from_square();
+    let mut side = board.side_to_move;
+
+    while depth < gain.len() - 1 {
+        // taking the attacker off the board uncovers any slider behind it
+        occupied &= !attacker_bit;
+        side = -side;
+
+        let attackers = attackers_to(board, to_square, side, occupied) & occupied;
+        if attackers == EMPTY_BITBOARD {
+            break;
+        }
+
+        // the least valuable attacker recaptures next
+        let mut attacker_piece = EMPTY;
+        for piece in PAWN..=KING {
+            let piece_attackers = attackers & board.piece_bitboards[piece_index(piece)];
+            if piece_attackers != EMPTY_BITBOARD {
+                attacker_piece = piece;
+                attacker_bit = piece_attackers & piece_attackers.wrapping_neg();
+                break;
+            }
+        }
+
+        // what the side recapturing has if the exchange stopped here
+        depth += 1;
+        gain[depth] = get_piece_value(piece_on_square) - gain[depth - 1];
+        piece_on_square = attacker_piece;
+        if attacker_piece == PAWN && promotes_on_square {
+            gain[depth] += get_piece_value(QUEEN) - get_piece_value(PAWN);
+            piece_on_square = QUEEN;
+        }
+    }
+
+    // either side can stop recapturing, so work back from the last capture in the list
+    while depth > 0 {
+        gain[depth - 1] = -std::cmp::max(-gain[depth - 1], gain[depth]);
+        depth -= 1;
+    }
+
+    return gain[0];
+}
+
+/// whether the exchange the move starts wins at least the threshold
+pub fn see_ge(board: &Board, chess_move: Move, threshold: i32) -> bool {
+    return see(board, chess_move) >= threshold;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::conversion;
+    use crate::evaluate::PIECE_VALUES;
+    use crate::see::*;
+
+    fn see_for(fen: &str, notation: &str) -> i32 {
+        let board = conversion::convert_fen_to_board(fen);
+        let chess_move = board.parse_move(notation).unwrap();
+        return see(&board, chess_move);
+    }
+
+    #[test]
+    fn undefended_piece_wins_its_value() {
+        assert_eq!(
+            see_for("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1", "e1e5"),
+            100
+        );
+    }
+
+    #[test]
+    fn defended_pawn_taken_by_knight_loses_the_knight() {
+        assert_eq!(
+            see_for(
+                "1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1",
+                "d3e5"
+            ),
+            100 - 320
+        );
+    }
+
+    #[test]
+    fn x_ray_attacker_joins_the_exchange() {
+        // the second white rook recaptures once the first has been taken
+        assert_eq!(
+            see_for("4k3/4r3/8/4p3/8/8/4R3/4R1K1 w - - 0 1", "e2e5"),
+            100
+        );
+    }
+
+    #[test]
+    fn see_suite() {
+        const P: i32 = PIECE_VALUES.pawn;
+        const N: i32 = PIECE_VALUES.knight;
+        const B: i32 = PIECE_VALUES.bishop;
+        const R: i32 = PIECE_VALUES.rook;
+        const Q: i32 = PIECE_VALUES.queen;
+
+        let suite = [
+            (
+                "6k1/1pp4p/p1pb4/6q1/3P1pRr/2P4P/PP1Br1P1/5RKN w - - 0 1",
+                "f1f4",
+                P - R + B,
+            ),
+            (
+                "5rk1/1pp2q1p/p1pb4/8/3P1NP1/2P5/1P1BQ1P1/5RK1 b - - 0 1",
+                "d6f4",
+                N - B,
+            ),
+            (
+                "4R3/2r3p1/5bk1/1p1r3p/p2PR1P1/P1BK1P2/1P6/8 b - - 0 1",
+                "h5g4",
+                0,
+            ),
+            (
+                "4R3/2r3p1/5bk1/1p1r1p1p/p2PR1P1/P1BK1P2/1P6/8 b - - 0 1",
+                "h5g4",
+                0,
+            ),
+            (
+                "4r1k1/5pp1/nbp4p/1p2p2q/1P2P1b1/1BP2N1P/1B2QPPK/3R4 b - - 0 1",
+                "g4f3",
+                N - B,
+            ),
+            (
+                "2r1r1k1/pp1bppbp/3p1np1/q3P3/2P2P2/1P2B3/P1N1B1PP/2RQ1RK1 b - - 0 1",
+                "d6e5",
+                P,
+            ),
+            (
+                "7r/5qpk/p1Qp1b1p/3r3n/BB3p2/5p2/P1P2P2/4RK1R w - - 0 1",
+                "e1e8",
+                0,
+            ),
+            (
+                "6rr/6pk/p1Qp1b1p/2n5/1B3p2/5p2/P1P2P2/4RK1R w - - 0 1",
+                "e1e8",
+                -R,
+            ),
+            (
+                "7r/5qpk/2Qp1b1p/1N1r3n/BB3p2/5p2/P1P2P2/4RK1R w - - 0 1",
+                "e1e8",
+                -R,
+            ),
+            // promotions, onto a defended and an undefended square
+            ("6RR/4bP2/8/8/5r2/3K4/5p2/4k3 w - - 0 1", "f7f8q", B - P),
+            ("6RR/4bP2/8/8/5r2/3K4/5p2/4k3 w - - 0 1", "f7f8n", N - P),
+            ("7R/5P2/8/8/6r1/3K4/5p2/4k3 w - - 0 1", "f7f8q", Q - P),
+            ("7R/5P2/8/8/6r1/3K4/5p2/4k3 w - - 0 1", "f7f8b", B - P),
+            ("7R/4bP2/8/8/1q6/3K4/5p2/4k3 w - - 0 1", "f7f8r", -P),
+            (
+                "8/4kp2/2npp3/1Nn5/1p2PQP1/7q/1PP1B3/4KR1r b - - 0 1",
+                "h1f1",
+                0,
+            ),
+            (
+                "8/4kp2/2npp3/1Nn5/1p2P1P1/7q/1PP1B3/4KR1r b - - 0 1",
+                "h1f1",
+                0,
+            ),
+            (
+                "2r2r1k/6bp/p7/2q2p1Q/3PpP2/1B6/P5PP/2RR3K b - - 0 1",
+                "c5c1",
+                2 * R - Q,
+            ),
+            (
+                "r2qk1nr/pp2ppbp/2b3p1/2p1p3/8/2N2N2/PPPP1PPP/R1BQR1K1 w kq - 0 1",
+                "f3e5",
+                P,
+            ),
+            (
+                "6r1/4kq2/b2p1p2/p1pPb3/p1P2B1Q/2P4P/2B1R1P1/6K1 w - - 0 1",
+                "f4e5",
+                0,
+            ),
+            // en passant, with and without a recapture
+            (
+                "3q2nk/pb1r1p2/np6/3P2Pp/2p1P3/2R4B/PQ3P1P/3R2K1 w - h6 0 1",
+                "g5h6",
+                0,
+            ),
+            (
+                "3q2nk/pb1r1p2/np6/3P2Pp/2p1P3/2R1B2B/PQ3P1P/3R2K1 w - h6 0 1",
+                "g5h6",
+                P,
+            ),
+            (
+                "2r4r/1P4pk/p2p1b1p/7n/BB3p2/2R2p2/P1P2P2/4RK2 w - - 0 1",
+                "c3c8",
+                R,
+            ),
+            (
+                "2r5/1P4pk/p2p1b1p/5b1n/BB3p2/2R2p2/P1P2P2/4RK2 w - - 0 1",
+                "c3c8",
+                R,
+            ),
+            (
+                "2r4k/2r4p/p7/2b2p1b/4pP2/1BR5/P1R3PP/2Q4K w - - 0 1",
+                "c3c5",
+                B,
+            ),
+            (
+                "8/pp6/2pkp3/4bp2/2R3b1/2P5/PP4B1/1K6 w - - 0 1",
+                "g2c6",
+                P - B,
+            ),
+        ];
+
+        for (fen, notation, expected) in suite {
+            assert_eq!(see_for(fen, notation), expected, "{} {}", fen, notation);
+
+            let board = conversion::convert_fen_to_board(fen);
+            let chess_move = board.parse_move(notation).unwrap();
+            assert!(see_ge(&board, chess_move, expected));
+            assert!(!see_ge(&board, chess_move, expected + 1));
+        }
+    }
+}
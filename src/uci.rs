@@ -1,14 +1,22 @@
 //! used to communicate with the engine
 
+use crate::attacks;
 use crate::bench;
 use crate::board::*;
+use crate::moves::Move;
+use crate::perft;
+use crate::pgn::{self, SearchComment};
 use crate::search::*;
-use crate::{conversion, evaluate};
+use crate::{conversion, evaluate, san};
 use std::io;
 
 const NAME: &str = "ChooChoo";
 const VERSION: &str = "0.1";
-const HELP: &str = "bench - run buit in bench";
+const HELP: &str = "bench - run buit in bench
+perftsuite <file> [depth] - check perft counts from an epd file
+d | fen - print the fen of the current position
+notation <san|uci> - show moves in san or uci notation
+savepgn <file> - save the game so far as pgn";
 const AUTHOR: &str = "Tierynn Byrnes";
 const CHOO_CHOO_TRAIN: &str = r"
 ____
@@ -27,6 +35,7 @@ pub enum CommandTypes {
     PrintState,
     Evaluate,
     Perft,
+    PerftSuite,
     MakeMove,
     MakeUnMake,
     Bench,
@@ -37,6 +46,8 @@ pub enum CommandTypes {
     Quit,    //
     Search,  //
     MoveList,
+    Notation,
+    SavePgn,
     Help,
 }
 
@@ -45,9 +56,22 @@ pub struct CommunicationManager {
     // debug_enabled: bool,
     board: Board,
     engine: SearchEngine,
+    options: UciCommandOptions,
+    /// the best move and its score from each search, by how many moves into the game it was,
+    /// so a saved pgn can comment the moves that were played
+    search_comments: Vec<(usize, Move, SearchComment)>,
 }
+#[derive(Default)]
 pub struct UciCommandOptions {
-    // no option supported atm
+    /// UCI_Chess960, read and write castling as the king taking its own rook
+    pub chess960: bool,
+    /// console output shows moves in san rather than coordinates. uci output never does
+    pub san_output: bool,
+}
+impl Default for CommunicationManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 impl CommunicationManager {
     pub fn new() -> Self {
@@ -56,6 +80,8 @@ impl CommunicationManager {
             // debug_enabled: false,
             board: Board::init(),
             engine: SearchEngine::new(),
+            options: UciCommandOptions::default(),
+            search_comments: Vec::new(),
         }
     }
     pub fn quit() {
@@ -79,9 +105,12 @@ impl CommunicationManager {
             "setoption" => CommandTypes::SetOption,
             "makeunmake" => CommandTypes::MakeUnMake,
             "movelist" => CommandTypes::MoveList,
+            "notation" => CommandTypes::Notation,
+            "savepgn" => CommandTypes::SavePgn,
             // "splitperft" => CommandTypes::SplitPerft,
-            // "perftsuite" => CommandTypes::PerftSuite,
+            "perftsuite" => CommandTypes::PerftSuite,
             "makemove" => CommandTypes::MakeMove,
+            "d" | "fen" => CommandTypes::GetFen,
             "bench" => CommandTypes::Bench,
             "help" => CommandTypes::Help,
             _ => {
@@ -91,43 +120,113 @@ impl CommunicationManager {
         }
     }
     pub fn position(&mut self, command_text: &str) {
+        let previous_start = self.board.game_start().get_fen();
+        let previous_moves = self.board.move_list.clone();
+
+        self.set_position(command_text);
+
+        // the comments are matched to moves by ply, so only the ones from the part of the game
+        // the new position shares with the old one still belong to it
+        if self.board.game_start().get_fen() != previous_start {
+            self.search_comments.clear();
+            return;
+        }
+        let shared_moves = previous_moves
+            .iter()
+            .zip(self.board.move_list.iter())
+            .take_while(|(previous, current)| previous == current)
+            .count();
+        self.search_comments
+            .retain(|(ply, _, _)| *ply <= shared_moves);
+    }
+    fn set_position(&mut self, command_text: &str) {
         // first token should be "position"
-        // second token should be "fen" or "startpos"ev
-        // if fen, is followed by a 6 space separated tokens
+        // second token should be "fen" or "startpos"
+        // if fen, is followed by up to 6 space separated tokens
 
         // after that, can be "moves".
         // if so, it can be followed by a list of moves.
-        let mut command_text_split = command_text.split_ascii_whitespace();
+        let mut command_text_split = command_text.split_ascii_whitespace().peekable();
         let _first_token = command_text_split.next().expect("no token");
-        let second_token = command_text_split.next().expect("no token");
 
-        if second_token == "startpos" {
-            self.board = Board::init();
+        match command_text_split.next() {
+            Some("startpos") => self.board = Board::init(),
+            Some("fen") => {
+                let mut fen_tokens = Vec::new();
+                while let Some(token) = command_text_split.next_if(|&token| token != "moves") {
+                    fen_tokens.push(token);
+                }
+                let mut board = match Board::from_fen(&fen_tokens.join(" ")) {
+                    Ok(board) => board,
+                    Err(error) => {
+                        // keep the position we had rather than playing on from a broken one
+                        println!("info string invalid fen: {}", error);
+                        return;
+                    }
+                };
+                // castling rights are checked by the rules of the variant being played
+                board.chess960 = self.options.chess960;
+                match board.validate(ValidationMode::Lenient) {
+                    Ok(repaired) => {
+                        for problem in repaired {
+                            println!("info string ignoring {:?}", problem);
+                        }
+                        self.board = board;
+                    }
+                    Err(problems) => {
+                        for problem in problems {
+                            println!("info string invalid position: {:?}", problem);
+                        }
+                        return;
+                    }
+                }
+            }
+            _ => {
+                println!("info string position needs startpos or fen");
+                return;
+            }
         }
 
-        if second_token == "fen" {
-            let mut fen_string = String::new();
-            for _fen_tokens in 1..7 {
-                fen_string
-                    .push_str(format!("{} ", command_text_split.next().unwrap_or("")).as_str());
+        // the moves after the position are written the way the gui was told to expect
+        self.board.chess960 = self.options.chess960;
+
+        if command_text_split.next() == Some("moves") {
+            for move_token in command_text_split {
+                // stop at the first bad move, the ones after it can't be right either
+                if let Err(error) = self.board.make_move_with_notation(move_token.to_string()) {
+                    println!("info string {}", error);
+                    return;
+                }
             }
-            println!("fen: {}", fen_string);
-            self.board = conversion::convert_fen_to_board(fen_string.as_str());
         }
-
-        let mut moves_token = second_token;
-        if second_token != "moves" {
-            moves_token = command_text_split.next().unwrap_or_default();
+    }
+    /// setoption name <name> value <value>, where the name can be more than one word
+    pub fn set_option(&mut self, command_text: &str) {
+        let mut name = Vec::new();
+        let mut value = Vec::new();
+        let mut reading_value = false;
+        for token in command_text.split_ascii_whitespace().skip(1) {
+            match token {
+                "name" => {}
+                "value" => reading_value = true,
+                _ if reading_value => value.push(token),
+                _ => name.push(token),
+            }
         }
 
-        // println!("moves token: {}", moves_token);
-        if moves_token == "moves" {
-            for move_token in command_text_split {
-                // println!("move: {}", move_token);
-                self.board
-                    .make_move_with_notation(move_token.to_string())
-                    .expect("invalid move");
+        match name.join(" ").as_str() {
+            "UCI_Chess960" => {
+                self.options.chess960 = value.join(" ") == "true";
+                self.board.chess960 = self.options.chess960;
             }
+            "Move Overhead" => {
+                if let Ok(move_overhead) = value.join(" ").parse::<u128>() {
+                    self.engine.move_overhead = move_overhead;
+                }
+            }
+            // only one value of each is supported
+            "Threads" | "Hash" => {}
+            other => println!("info string unsupported option: {}", other),
         }
     }
     pub fn evaluate(&self) {
@@ -155,11 +254,18 @@ impl CommunicationManager {
                     self.engine.nodes as f32 / self.engine.start.elapsed().as_secs_f32()
                 );
 
+                // a mated or stalemated position has no root moves at all
+                let Some(best) = outcome.1.first() else {
+                    println!("no legal moves");
+                    return;
+                };
+                self.record_search_comment(best.best_move, best.best_score);
+
                 // get random move from best moves with matching top score.
                 println!(
                     "best move {}, score {}",
-                    conversion::convert_move_to_notation(&outcome.1[0].best_move),
-                    outcome.1[0].best_score
+                    self.human_notation(&self.board, best.best_move),
+                    best.best_score
                 );
             }
         }
@@ -171,27 +277,92 @@ impl CommunicationManager {
         match command_token_split.next() {
             None => println!("no more commands"),
             Some(arg_2) => {
+                let board_before = self.board.clone();
                 let outcome = self.board.make_move_with_notation(arg_2.to_string());
                 match outcome {
                     Ok(m) => {
                         println!(
                             "made the mode: from {},{}, to: {},{}, notation: {}",
-                            m.from.0,
-                            m.from.1,
-                            m.to.0,
-                            m.to.1,
-                            conversion::convert_move_to_notation(&m)
+                            m.from().0,
+                            m.from().1,
+                            m.to().0,
+                            m.to().1,
+                            self.human_notation(&board_before, m)
                         );
-                        println!("piece that move {}", m.from_piece);
-                        println!(" to piece  {}", m.to_piece);
+                        println!("piece that move {}", self.board.get_piece(m.to()));
+                        let captured_piece =
+                            self.board.ply_record.last().map(|ply| ply.captured_piece);
+                        println!(" to piece  {}", captured_piece.unwrap_or_default());
                         // board.un_make_move(m);
                         print_board(&self.board);
+                        if let Some(game_outcome) = self.board.outcome() {
+                            println!("game over: {:?} {}", game_outcome, game_outcome.result());
+                        }
                     }
                     Err(e) => println!("{}", e),
                 }
             }
         }
     }
+    fn record_search_comment(&mut self, best_move: Move, score: i32) {
+        let comment = SearchComment {
+            score,
+            depth: self.engine.current_depth,
+        };
+        self.search_comments
+            .push((self.board.move_list.len(), best_move, comment));
+    }
+    /// savepgn <file>, the game so far with the search's view of any move it suggested
+    pub fn save_pgn(&self, command_text: &str) {
+        let Some(path) = command_text.split_ascii_whitespace().nth(1) else {
+            println!("savepgn needs a file name");
+            return;
+        };
+
+        let comments: Vec<Option<SearchComment>> = self
+            .board
+            .move_list
+            .iter()
+            .enumerate()
+            .map(|(index, played)| {
+                self.search_comments
+                    .iter()
+                    .rev()
+                    .find(|(ply, best_move, _)| *ply == index && best_move == played)
+                    .map(|(_, _, comment)| *comment)
+            })
+            .collect();
+        let tags = vec![("Event".to_string(), format!("{} game", NAME))];
+
+        match std::fs::write(path, pgn::write_pgn(&self.board, &tags, &comments)) {
+            Ok(()) => println!("saved pgn to {}", path),
+            Err(error) => println!("could not save pgn to {}: {}", path, error),
+        }
+    }
+    /// a move from the position on the board for people to read, in san if that was asked for
+    fn human_notation(&self, board: &Board, chess_move: Move) -> String {
+        if self.options.san_output {
+            return san::move_to_san(board, chess_move);
+        }
+        return conversion::convert_move_to_uci_notation(&chess_move, board.chess960);
+    }
+    /// notation san|uci, how the console shows moves
+    pub fn set_notation(&mut self, command_text: &str) {
+        match command_text.split_ascii_whitespace().nth(1) {
+            Some("san") => self.options.san_output = true,
+            Some("uci") => self.options.san_output = false,
+            _ => println!("notation should be san or uci"),
+        }
+    }
+    pub fn print_move_list(&self) {
+        if self.options.san_output {
+            println!("{}", san::game_to_san(&self.board).join(" "));
+            return;
+        }
+        for move_item in &self.board.move_list {
+            println!("move from:{:?}, to: {:?}", move_item.from(), move_item.to());
+        }
+    }
     pub fn make_unmake_move(&mut self, command_text: &str) {
         let mut command_token_split = command_text.split_ascii_whitespace();
         let _first_token = command_token_split.next().expect("no token");
@@ -199,21 +370,52 @@ impl CommunicationManager {
         match command_token_split.next() {
             None => println!("no more commands"),
             Some(arg_2) => {
-                let move_to_do = self.board.convert_notation_to_move(arg_2.to_string());
-                self.board.make_move(move_to_do.as_ref().unwrap());
+                let move_to_do = match self.board.parse_move(arg_2) {
+                    Ok(move_to_do) => move_to_do,
+                    Err(error) => {
+                        println!("{}", error);
+                        return;
+                    }
+                };
+                self.board.make_move(move_to_do);
                 print_board(&self.board);
-                self.board.un_make_move(move_to_do.as_ref().unwrap());
+                self.board.un_make_move(move_to_do);
                 print_board(&self.board);
             }
         }
     }
+    /// `perft <depth> [threads <n>] [hash <mb>] [verify]`. asking for threads or a hash size
+    /// splits the root moves across threads sharing a hash table, and verify checks those counts
+    /// against a run without the table
     pub fn perft(&mut self, command_text: &str) {
-        let depth: i8 = command_text
-            .split_ascii_whitespace()
-            .nth(1)
+        let mut command_text_split = command_text.split_ascii_whitespace().skip(1);
+        let depth: i8 = command_text_split
+            .next()
             .expect("Invalid depth value")
             .parse()
             .expect("Invalid depth value");
+
+        let mut threads = None;
+        let mut hash_megabytes = None;
+        let mut verify = false;
+        while let Some(token) = command_text_split.next() {
+            match token {
+                "threads" => threads = command_text_split.next().and_then(|n| n.parse().ok()),
+                "hash" => hash_megabytes = command_text_split.next().and_then(|n| n.parse().ok()),
+                "verify" => verify = true,
+                _ => println!("unknown perft option: {}", token),
+            }
+        }
+        if threads.is_some() || hash_megabytes.is_some() || verify {
+            self.parallel_perft(
+                depth,
+                threads.unwrap_or(1),
+                hash_megabytes.unwrap_or(perft::DEFAULT_PERFT_HASH_MEGABYTES),
+                verify,
+            );
+            return;
+        }
+
         self.engine = SearchEngine::new();
         let nodes = self.engine.perft(&mut self.board, depth, true);
         // println!("total nodes: {}", self.engine.nodes);
@@ -226,6 +428,52 @@ impl CommunicationManager {
         println!("perft nodes: {}", nodes);
         println!()
     }
+    fn parallel_perft(&self, depth: i8, threads: usize, hash_megabytes: usize, verify: bool) {
+        // a size of 0 turns the table off
+        let table = (hash_megabytes > 0).then(|| perft::PerftHashTable::new(hash_megabytes));
+
+        let start = std::time::Instant::now();
+        let root_counts = perft::parallel_perft(&self.board, depth, threads, table.as_ref());
+        let seconds = start.elapsed().as_secs_f64();
+
+        let nodes: u64 = root_counts.iter().map(|(_, nodes)| nodes).sum();
+        for (root_move, nodes) in root_counts.iter() {
+            println!(
+                "{} - {}",
+                conversion::convert_move_to_uci_notation(root_move, self.board.chess960),
+                nodes
+            );
+        }
+        println!("root moves: {}", root_counts.len());
+        println!("perft nodes: {}", nodes);
+        println!(
+            "time: {:.3}s, nodes per second: {:.0}",
+            seconds,
+            nodes as f64 / seconds
+        );
+
+        if verify {
+            let uncached_counts = perft::parallel_perft(&self.board, depth, threads, None);
+            let mut mismatches = 0;
+            for ((root_move, nodes), (_, uncached_nodes)) in
+                root_counts.iter().zip(uncached_counts.iter())
+            {
+                if nodes != uncached_nodes {
+                    mismatches += 1;
+                    println!(
+                        "mismatch {}: {} with the hash table, {} without",
+                        conversion::convert_move_to_uci_notation(root_move, self.board.chess960),
+                        nodes,
+                        uncached_nodes
+                    );
+                }
+            }
+            if mismatches == 0 {
+                println!("verified against the uncached counts");
+            }
+        }
+        println!()
+    }
     pub fn enable_uci(&mut self) {
         self.uci_enabled = true;
         println!("id name {}", NAME);
@@ -235,6 +483,7 @@ impl CommunicationManager {
         println!("option name Move Overhead type spin default 10 min 0 max 2000");
         println!("option name Threads type spin default 1 min 1 max 1");
         println!("option name Hash type spin default 0 min 0 max 0");
+        println!("option name UCI_Chess960 type check default false");
         println!("uciok");
         // output all the options curently supported
     }
@@ -277,44 +526,84 @@ impl CommunicationManager {
                 _ => {}
             }
         }
+
+        // with no legal moves there is nothing to search or comment on. a draw by the fifty move
+        // rule or repetition can still be played on if the gui wants to
+        if let Some(GameOutcome::Checkmate { .. } | GameOutcome::Stalemate) = self.board.outcome() {
+            println!("bestmove 0000");
+            return;
+        }
         let moves = self.engine.search(&mut self.board);
 
         let time_taken_seconds = self.engine.start.elapsed().as_secs_f32();
 
+        // the root moves come back best first
+        let best_score = moves.1.first().map_or(0, |best| best.best_score);
+        self.record_search_comment(moves.0, best_score);
+
         println!(
             "info depth {} time {} nodes {} nps {} score cp {:.2}",
             self.engine.current_depth,
             self.engine.start.elapsed().as_millis(),
             self.engine.nodes,
             self.engine.nodes as f32 / time_taken_seconds,
-            moves.0.search_score,
+            best_score,
         );
         println!(
             "bestmove {}",
-            conversion::convert_array_location_to_notation(
-                moves.0.from,
-                moves.0.to,
-                Some(match moves.0.promotion_to.unwrap_or(0) {
-                    1 => 'p'.to_string(),
-                    2 => 'n'.to_string(),
-                    3 => 'b'.to_string(),
-                    4 => 'r'.to_string(),
-                    5 => 'q'.to_string(),
-                    6 => 'k'.to_string(),
-                    0 => ' '.to_string(),
-                    _ => ' '.to_string(),
-                })
-            )
+            conversion::convert_move_to_uci_notation(&moves.0, self.board.chess960)
         );
         // return moves[0];
         // do the search with the provided settings
     }
 }
 
+/// the perftree debugging tool's contract: `choo_choo <depth> "<fen>" ["<moves>"]` gives each
+/// root move with its node count, a blank line and then the total
+pub fn perftree(arguments: &[String]) -> Result<String, String> {
+    let [depth, fen, moves @ ..] = arguments else {
+        return Err("usage: choo_choo <depth> \"<fen>\" [\"<moves>\"]".to_string());
+    };
+    if moves.len() > 1 {
+        return Err(format!("unexpected argument '{}'", moves[1]));
+    }
+
+    attacks::init();
+
+    let depth: i8 = depth
+        .parse()
+        .map_err(|_| format!("invalid depth '{}'", depth))?;
+    let mut board = Board::from_fen(fen).map_err(|error| format!("invalid fen: {}", error))?;
+    board
+        .validate(ValidationMode::Lenient)
+        .map_err(|problems| format!("invalid position: {:?}", problems))?;
+    for notation in moves
+        .iter()
+        .flat_map(|moves| moves.split_ascii_whitespace())
+    {
+        board
+            .make_move_with_notation(notation.to_string())
+            .map_err(|error| error.to_string())?;
+    }
+
+    let mut engine = SearchEngine::new();
+    let nodes = engine.perft(&mut board, depth, true);
+
+    let mut output = String::new();
+    for root in engine.move_nodes.iter() {
+        output.push_str(&format!("{} {}\n", root.move_notation, root.nodes));
+    }
+    output.push_str(&format!("\n{}", nodes));
+    return Ok(output);
+}
+
 pub fn run() {
     println!("{} {}", NAME, VERSION);
     println!("{}", CHOO_CHOO_TRAIN);
 
+    // build the attack tables before the first search needs them
+    attacks::init();
+
     let stdin = io::stdin();
 
     let mut manager = CommunicationManager::new();
@@ -341,20 +630,28 @@ pub fn run() {
             CommandTypes::MakeMove => manager.make_move(&buffer),
             CommandTypes::MakeUnMake => manager.make_unmake_move(&buffer),
             CommandTypes::Perft => manager.perft(&buffer),
-            CommandTypes::Evaluate => manager.evaluate(),
-            CommandTypes::NewGame => manager.board.reset_board(),
-            CommandTypes::PrintState => print_board(&manager.board),
-            CommandTypes::UciNewGame => {} // do nothing
-            CommandTypes::MoveList => {
-                for move_item in &manager.board.move_list {
-                    println!("move from:{:?}, to: {:?}", move_item.from, move_item.to);
+            CommandTypes::PerftSuite => {
+                if let Err(error) =
+                    perft::perft_suite_command(buffer.split_ascii_whitespace().skip(1))
+                {
+                    println!("{}", error);
                 }
             }
+            CommandTypes::Evaluate => manager.evaluate(),
+            CommandTypes::NewGame => {
+                manager.board.reset_board();
+                manager.search_comments.clear();
+            }
+            CommandTypes::PrintState => print_board(&manager.board),
+            CommandTypes::UciNewGame => manager.search_comments.clear(),
+            CommandTypes::MoveList => manager.print_move_list(),
+            CommandTypes::Notation => manager.set_notation(&buffer),
+            CommandTypes::SavePgn => manager.save_pgn(&buffer),
             CommandTypes::Invalid => {
                 println!("invalid or unsupported command");
                 println!("{}", &buffer);
             }
-            CommandTypes::SetOption => {}
+            CommandTypes::SetOption => manager.set_option(&buffer),
             CommandTypes::Bench => bench::bench(), //manager.bench(),
             CommandTypes::IsReady => println!("readyok"),
             CommandTypes::Go => manager.go(&buffer),
@@ -362,7 +659,7 @@ pub fn run() {
             CommandTypes::Hash => {
                 println!(
                     "hash: {}, has repeated: {}",
-                    conversion::hash_board_state(&manager.board),
+                    manager.board.zobrist_key,
                     manager.board.has_positions_repeated()
                 );
 
@@ -380,3 +677,78 @@ pub fn run() {
         buffer.clear();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::uci::*;
+
+    fn perftree_for(arguments: &[&str]) -> Result<String, String> {
+        let arguments: Vec<String> = arguments
+            .iter()
+            .map(|argument| argument.to_string())
+            .collect();
+        return perftree(&arguments);
+    }
+
+    #[test]
+    fn perftree_divides_after_the_moves() {
+        let output = perftree_for(&[
+            "2",
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "e2e4 e7e5",
+        ])
+        .unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+
+        // 29 root moves, a blank line and the total
+        assert_eq!(lines.len(), 31);
+        assert!(lines.contains(&"g1f3 29"));
+        assert_eq!(lines[29], "");
+        let total: i128 = lines[..29]
+            .iter()
+            .map(|line| line.split(' ').nth(1).unwrap().parse::<i128>().unwrap())
+            .sum();
+        assert_eq!(lines[30], total.to_string());
+    }
+
+    #[test]
+    fn search_comments_only_survive_in_the_same_game() {
+        let mut manager = CommunicationManager::new();
+        manager.position("position startpos moves e2e4");
+        let reply = manager.board.parse_move("e7e5").unwrap();
+        manager.record_search_comment(reply, 20);
+
+        // the gui sends the whole game again with the next move
+        manager.position("position startpos moves e2e4 e7e5 g1f3");
+        assert_eq!(manager.search_comments.len(), 1);
+
+        // a different game that happens to reach the same ply
+        manager.position("position startpos moves d2d4");
+        assert!(manager.search_comments.is_empty());
+
+        manager.position("position startpos moves d2d4");
+        let reply = manager.board.parse_move("d7d5").unwrap();
+        manager.record_search_comment(reply, 20);
+        manager.position("position fen 4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+        assert!(manager.search_comments.is_empty());
+    }
+
+    #[test]
+    fn go_without_legal_moves_leaves_no_comment() {
+        let mut manager = CommunicationManager::new();
+        manager.position("position fen 7k/6Q1/6K1/8/8/8/8/8 b - - 0 1");
+        manager.go("go depth 2");
+        assert!(manager.search_comments.is_empty());
+    }
+
+    #[test]
+    fn perftree_rejects_bad_arguments() {
+        let fen = "4k3/8/8/8/8/8/8/4K3 w - - 0 1";
+        assert!(perftree_for(&["1", fen]).is_ok());
+        assert!(perftree_for(&["one", fen]).is_err());
+        assert!(perftree_for(&["1", "not a fen"]).is_err());
+        assert!(perftree_for(&["1", "P3k3/8/8/8/8/8/8/4K3 w - - 0 1", ""]).is_err());
+        assert!(perftree_for(&["1", fen, "e1e3"]).is_err());
+        assert!(perftree_for(&["1"]).is_err());
+    }
+}
@@ -0,0 +1,155 @@
+//! zobrist keys used to hash positions
+//!
+//! the keys are generated at compile time from a fixed seed, so a position always hashes to the
+//! same value.
+use crate::bitboard::*;
+use crate::board::Board;
+use crate::constants::*;
+
+pub struct ZobristKeys {
+    /// indexed by colour index, piece index and square
+    pub pieces: [[[u64; 64]; 6]; 2],
+    pub black_to_move: u64,
+    /// one key per castling right, indexed by colour index then castling side
+    pub castling: [[u64; 2]; 2],
+    pub en_passant_file: [u64; 8],
+}
+
+pub static ZOBRIST_KEYS: ZobristKeys = generate_keys();
+
+const fn next_random(state: u64) -> u64 {
+    // xorshift64
+    let mut state = state;
+    state ^= state << 13;
+    state ^= state >> 7;
+    state ^= state << 17;
+    return state;
+}
+
+const fn generate_keys() -> ZobristKeys {
+    let mut keys = ZobristKeys {
+        pieces: [[[0; 64]; 6]; 2],
+        black_to_move: 0,
+        castling: [[0; 2]; 2],
+        en_passant_file: [0; 8],
+    };
+    let mut state: u64 = 0x4A6F_6E61_7468_616E;
+
+    let mut colour = 0;
+    while colour < 2 {
+        let mut piece = 0;
+        while piece < 6 {
+            let mut square = 0;
+            while square < 64 {
+                state = next_random(state);
+                keys.pieces[colour][piece][square] = state;
+                square += 1;
+            }
+            piece += 1;
+        }
+        colour += 1;
+    }
+
+    state = next_random(state);
+    keys.black_to_move = state;
+
+    let mut index = 0;
+    while index < 4 {
+        state = next_random(state);
+        keys.castling[index / 2][index % 2] = state;
+        index += 1;
+    }
+
+    let mut file = 0;
+    while file < 8 {
+        state = next_random(state);
+        keys.en_passant_file[file] = state;
+        file += 1;
+    }
+
+    return keys;
+}
+
+pub fn piece_key(piece: i8, colour: i8, location: (usize, usize)) -> u64 {
+    return ZOBRIST_KEYS.pieces[colour_index(colour)][piece_index(piece)]
+        [square_from_location(location)];
+}
+
+/// the combined key for whichever castling rights the board still has
+pub fn castling_key(board: &Board) -> u64 {
+    let mut key = 0;
+    for (colour, rook_files) in board.castling_rook_files.iter().enumerate() {
+        for (castle_side, rook_file) in rook_files.iter().enumerate() {
+            if rook_file.is_some() {
+                key ^= ZOBRIST_KEYS.castling[colour][castle_side];
+            }
+        }
+    }
+    return key;
+}
+
+/// the en passant file only counts if the side to move has a pawn that could capture,
+/// otherwise the same position would hash differently depending on how it was reached
+pub fn en_passant_key(board: &Board) -> u64 {
+    let Some(en_passant_location) = board.en_passant_location else {
+        return 0;
+    };
+
+    // the pawns that could capture stand either side of the pawn that just moved two squares
+    let beside =
+        adjacent_on_rank(en_passant_location) & board.piece_bitboard(PAWN, board.side_to_move);
+
+    if beside == EMPTY_BITBOARD {
+        return 0;
+    }
+    return ZOBRIST_KEYS.en_passant_file[en_passant_location.1];
+}
+
+fn adjacent_on_rank(location: (usize, usize)) -> u64 {
+    let bit = square_bit(location);
+    return ((bit << 1) & !FILE_A) | ((bit >> 1) & !FILE_H);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::board::Board;
+    use crate::conversion;
+
+    #[test]
+    fn castling_rights_change_the_hash() {
+        let with_rights = conversion::convert_fen_to_board("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        let without_rights =
+            conversion::convert_fen_to_board("r3k2r/8/8/8/8/8/8/R3K2R w Kkq - 0 1");
+        assert_ne!(with_rights.zobrist_key, without_rights.zobrist_key);
+    }
+
+    #[test]
+    fn en_passant_only_counts_when_capture_possible() {
+        let no_capture = conversion::convert_fen_to_board("4k3/8/8/8/4P3/8/8/4K3 b - e3 0 1");
+        let no_square = conversion::convert_fen_to_board("4k3/8/8/8/4P3/8/8/4K3 b - - 0 1");
+        assert_eq!(no_capture.zobrist_key, no_square.zobrist_key);
+
+        let capture = conversion::convert_fen_to_board("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1");
+        let capture_no_square =
+            conversion::convert_fen_to_board("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1");
+        assert_ne!(capture.zobrist_key, capture_no_square.zobrist_key);
+    }
+
+    #[test]
+    fn hash_follows_moves_and_returns_after_unmake() {
+        let mut board = Board::init();
+        let start_key = board.zobrist_key;
+
+        let mut moves = vec![];
+        for notation in ["g1f3", "g8f6", "f3g1", "f6g8"] {
+            moves.push(board.make_move_with_notation(notation.to_string()).unwrap());
+        }
+        // same pieces, same side to move and same castling rights
+        assert_eq!(board.zobrist_key, start_key);
+
+        for chess_move in moves.iter().rev() {
+            board.un_make_move(*chess_move);
+        }
+        assert_eq!(board.zobrist_key, start_key);
+    }
+}